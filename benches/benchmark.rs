@@ -20,7 +20,7 @@ fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| {
             rt.block_on(async {
                 for _ in 0..ITERATIONS {
-                    let _ = client.query("SELECT 1").await.unwrap();
+                    let _ = client.query("SELECT 1", &[]).await.unwrap();
                 }
             });
         })
@@ -53,7 +53,7 @@ fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| {
             rt.block_on(async {
                 for _ in 0..ITERATIONS {
-                    let _ = client.query("SELECT 1, 2, 3, 4, 5, 6, 7, 8, 9, 10").await.unwrap();
+                    let _ = client.query("SELECT 1, 2, 3, 4, 5, 6, 7, 8, 9, 10", &[]).await.unwrap();
                 }
             });
         })
@@ -86,7 +86,7 @@ fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| {
             rt.block_on(async {
                 for _ in 0..ITERATIONS {
-                    let _ = client.query("SELECT generate_series(1, 100)").await.unwrap();
+                    let _ = client.query("SELECT generate_series(1, 100)", &[]).await.unwrap();
                 }
             });
         })
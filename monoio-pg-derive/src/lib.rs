@@ -0,0 +1,84 @@
+//! Derive macros for `monoio-pg`, re-exported by it under the `derive`
+//! feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input};
+
+/// Implements `FromRow` for a struct with named fields, reading each field
+/// from the column of the same name.
+///
+/// `#[from_row(rename = "column")]` on a field reads it from a differently
+/// named column.
+#[proc_macro_derive(FromRow, attributes(from_row))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_row(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_from_row(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input,
+                    "FromRow can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input,
+                "FromRow can only be derived for structs",
+            ));
+        }
+    };
+
+    let mut reads = Vec::with_capacity(fields.len());
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let column = column_name(field)?.unwrap_or_else(|| {
+            let name = ident.to_string();
+            name.strip_prefix("r#").unwrap_or(&name).to_string()
+        });
+        let ty = &field.ty;
+        reads.push(quote! {
+            #ident: row.try_get::<_, #ty>(#column)?
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::monoio_pg::FromRow for #name #ty_generics #where_clause {
+            fn from_row(row: &::monoio_pg::client::Row) -> ::monoio_pg::Result<Self> {
+                ::std::result::Result::Ok(Self {
+                    #(#reads,)*
+                })
+            }
+        }
+    })
+}
+
+/// Returns the column given by `#[from_row(rename = "...")]`, if any.
+fn column_name(field: &syn::Field) -> syn::Result<Option<String>> {
+    let mut rename = None;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("from_row") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported from_row attribute"))
+            }
+        })?;
+    }
+    Ok(rename)
+}
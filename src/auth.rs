@@ -56,12 +56,12 @@ impl ScramClient {
         let mut i = None;
 
         for part in server_first.split(',') {
-            if part.starts_with("r=") {
-                r = Some(&part[2..]);
-            } else if part.starts_with("s=") {
-                s = Some(&part[2..]);
-            } else if part.starts_with("i=") {
-                i = Some(&part[2..]);
+            if let Some(v) = part.strip_prefix("r=") {
+                r = Some(v);
+            } else if let Some(v) = part.strip_prefix("s=") {
+                s = Some(v);
+            } else if let Some(v) = part.strip_prefix("i=") {
+                i = Some(v);
             }
         }
 
@@ -81,7 +81,7 @@ impl ScramClient {
             .map_err(|e| Error::Authentication(e.to_string()))?;
 
         let client_key = hmac_sha256(&salted_password, b"Client Key");
-        let stored_key = Sha256::digest(&client_key);
+        let stored_key = Sha256::digest(client_key);
 
         let client_final_message_without_proof = format!("c=biws,r={}", r);
         let auth_message = format!(
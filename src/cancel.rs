@@ -0,0 +1,95 @@
+use crate::error::{Error, Result};
+use crate::sqlstate::SqlState;
+use crate::stream;
+use crate::tls::TlsConfig;
+use bytes::BytesMut;
+use monoio::io::{AsyncReadRent, AsyncWriteRent, AsyncWriteRentExt};
+use postgres_protocol::message::frontend;
+use std::future::Future;
+use std::time::Duration;
+
+/// A handle for cancelling whatever query is running on a connection.
+///
+/// Created by [`Client::cancel_token`](crate::Client::cancel_token). Unlike
+/// the client it is `Send`, so it can be handed to another task or to another
+/// thread's runtime. Cancelling opens a separate connection to the server,
+/// using TLS if the original connection's settings ask for it.
+///
+/// Cancellation is best effort: if the query finishes before the request
+/// arrives, nothing happens, and if the connection has moved on to another
+/// query in the meantime, that one is cancelled instead.
+#[derive(Clone, Debug)]
+pub struct CancelToken {
+    host: String,
+    port: u16,
+    tls: TlsConfig,
+    /// The backend's process ID and secret key from `BackendKeyData`, which
+    /// some poolers do not send.
+    key: Option<(i32, i32)>,
+}
+
+impl CancelToken {
+    pub(crate) fn new(host: &str, port: u16, tls: TlsConfig, key: Option<(i32, i32)>) -> Self {
+        Self {
+            host: host.to_string(),
+            port,
+            tls,
+            key,
+        }
+    }
+
+    /// Returns the process ID of the server backend the connection talks to.
+    pub fn process_id(&self) -> Option<i32> {
+        self.key.map(|(process_id, _)| process_id)
+    }
+
+    /// Asks the server to cancel the query running on the connection. The
+    /// query itself then fails with [`SqlState::QueryCanceled`].
+    pub async fn cancel_query(&self) -> Result<()> {
+        let (process_id, secret_key) = self
+            .key
+            .ok_or_else(|| Error::Other("the server sent no cancellation key".into()))?;
+
+        let mut stream = stream::connect(&self.host, self.port, &self.tls, None).await?;
+        let mut buf = BytesMut::new();
+        frontend::cancel_request(process_id, secret_key, &mut buf);
+        let (res, _) = stream.write_all(buf).await;
+        res?;
+        stream.flush().await?;
+
+        // The server closes the connection without replying, and without a
+        // TLS close_notify, once it has handled the request. Wait for that
+        // before returning, whatever form it takes.
+        let _ = stream.read(Vec::with_capacity(1)).await;
+        Ok(())
+    }
+
+    /// Awaits `future`, a query on the connection this token belongs to, for
+    /// at most `duration`. If it takes longer, the query is cancelled and the
+    /// rest of its response is still read, so the connection is left ready
+    /// for the next query.
+    ///
+    /// Returns [`Error::Timeout`] if the query was cancelled. A query that
+    /// finished before the cancellation took effect returns its result as
+    /// usual. The runtime must have the timer enabled.
+    pub async fn timeout<F, T>(&self, duration: Duration, future: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let mut future = std::pin::pin!(future);
+        if let Ok(res) = monoio::time::timeout(duration, &mut future).await {
+            return res;
+        }
+
+        if let Err(e) = self.cancel_query().await {
+            tracing::warn!("failed to cancel timed out query: {}", e);
+        }
+        match future.await {
+            Err(e) if e.code() == Some(&SqlState::QueryCanceled) => Err(Error::Timeout(format!(
+                "after {:?} waiting for the query, which was cancelled",
+                duration
+            ))),
+            res => res,
+        }
+    }
+}
@@ -1,7 +1,18 @@
+use crate::cancel::CancelToken;
+use crate::config::Config;
 use crate::connection::Connection;
-use crate::error::Result;
+use crate::copy::{CopyInSink, CopyOutStream};
+use crate::error::{DbError, Error, Result};
+use crate::notification::Notifications;
+use crate::pipeline::Pipeline;
+use crate::row::RowIndex;
+use crate::row_stream::RowStream;
+use crate::simple_query::SimpleQueryResult;
+use crate::statement::{Statement, ToStatement};
+use crate::transaction::{Transaction, TransactionBuilder};
 use bytes::Bytes;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub struct Client {
     connection: Connection,
@@ -14,31 +25,369 @@ impl Client {
         password: Option<&str>,
         database: Option<&str>,
     ) -> Result<Self> {
-        let connection = Connection::connect(addr, user, password, database).await?;
+        Self::connect_with_config(&Config::from_addr(addr, user, password, database)).await
+    }
+
+    /// Connects using every setting in `config`, including TLS.
+    pub async fn connect_with_config(config: &Config) -> Result<Self> {
+        let connection = Connection::connect(config).await?;
         Ok(Self { connection })
     }
 
-    pub async fn execute(&mut self, query: &str) -> Result<()> {
-        self.connection.execute(query).await
+    /// Prepares `query` and returns a handle to it, with the parameter types
+    /// and result columns described by the server.
+    ///
+    /// The query methods already prepare and cache SQL text on their own;
+    /// this is for keeping a statement explicitly or inspecting its types.
+    pub async fn prepare(&mut self, query: &str) -> Result<Statement> {
+        self.connection.prepare(query).await
+    }
+
+    /// Like [`Client::prepare`], but with the types of the first
+    /// `types.len()` parameters given instead of inferred by the server.
+    pub async fn prepare_typed(&mut self, query: &str, types: &[Type]) -> Result<Statement> {
+        self.connection.prepare_typed(query, types).await
+    }
+
+    /// Closes `statement` on the server right away. Dropping the last handle
+    /// to a statement also closes it, but only before the next request.
+    pub async fn close_statement(&mut self, statement: Statement) -> Result<()> {
+        self.connection.close_statement(statement).await
+    }
+
+    /// Returns the value of a setting the server reports to clients, such as
+    /// `server_version`, `server_encoding`, `TimeZone`, `DateStyle`,
+    /// `integer_datetimes` or `application_name`. Changes made during the
+    /// session, for example with `SET TimeZone`, are reflected.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.connection.parameter(name)
+    }
+
+    /// Returns the server version in the form of `server_version_num`, such
+    /// as `160002` for 16.2 or `90624` for 9.6.24.
+    pub fn server_version(&self) -> Option<u32> {
+        self.parameter("server_version")
+            .and_then(parse_server_version)
+    }
+
+    /// Returns whether the client is in a transaction block, as of the end of
+    /// its last request.
+    pub fn transaction_status(&self) -> TransactionStatus {
+        self.connection.transaction_status()
+    }
+
+    /// Replaces the function that receives notices from the server on this
+    /// client, which is initially [`Config::notice_handler`].
+    pub fn set_notice_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&DbError) + Send + Sync + 'static,
+    {
+        self.connection.set_notice_handler(Arc::new(handler));
+    }
+
+    /// Subscribes to notifications sent with `NOTIFY` on `channel`. They are
+    /// read with [`Client::notifications`].
+    pub async fn listen(&mut self, channel: &str) -> Result<()> {
+        self.batch_execute(&format!("LISTEN {}", quote_identifier(channel)))
+            .await
+    }
+
+    /// Stops listening on `channel`. Notifications already received stay
+    /// buffered.
+    pub async fn unlisten(&mut self, channel: &str) -> Result<()> {
+        self.batch_execute(&format!("UNLISTEN {}", quote_identifier(channel)))
+            .await
+    }
+
+    /// Returns a stream of the notifications received on the channels this
+    /// client listens on, starting with any that arrived during earlier
+    /// queries.
+    ///
+    /// To wait with a deadline, use [`Notifications::next_timeout`]. Dropping
+    /// a pending `next()` instead, as `monoio::time::timeout` does, closes the
+    /// client.
+    pub fn notifications(&mut self) -> Notifications<'_> {
+        Notifications::new(&mut self.connection)
+    }
+
+    /// Sets how long a query may run before the client cancels it, replacing
+    /// the [`Config::statement_timeout`] it was created with. `None` removes
+    /// the limit.
+    ///
+    /// It covers `execute`, `query`, `query_with_tag`, `batch_execute` and
+    /// `simple_query`, and the same methods on transactions.
+    pub fn set_statement_timeout(&mut self, timeout: Option<Duration>) {
+        self.connection.set_statement_timeout(timeout);
+    }
+
+    /// Ends the session cleanly: sends `Terminate` and waits for the server
+    /// to close the connection. Dropping a client closes the socket without
+    /// telling the server, which logs it as an unexpected EOF.
+    pub async fn close(self) -> Result<()> {
+        self.connection.close().await
+    }
+
+    /// Returns whether the connection is unusable, because the server closed
+    /// it, an I/O or protocol error left it in an unknown state, or a request
+    /// future was dropped while it was reading or writing. Every request on a
+    /// closed client fails with [`Error::Broken`](crate::Error::Broken), and a
+    /// [`Pool`](crate::Pool) discards it.
+    ///
+    /// A request that is dropped between messages, or that fails part way
+    /// through a response, does not close the client: the rest of the
+    /// response is read and discarded before the next request.
+    pub fn is_closed(&self) -> bool {
+        self.connection.is_closed()
+    }
+
+    /// Returns a token that can cancel the query running on this client, from
+    /// this task or any other. See [`CancelToken`].
+    pub fn cancel_token(&self) -> CancelToken {
+        self.connection.cancel_token()
+    }
+
+    /// Empties the cache of statements prepared for SQL text. They are closed
+    /// on the server before the next request.
+    ///
+    /// Custom types looked up on the server are forgotten as well, which is
+    /// needed after `ALTER TYPE` changes one, such as adding an enum label.
+    pub fn clear_statement_cache(&mut self) {
+        self.connection.clear_statement_cache();
+    }
+
+    /// Executes a statement and returns the number of rows it affected.
+    pub async fn execute<T>(&mut self, statement: &T, params: &[&(dyn ToSql + Sync)]) -> Result<u64>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.connection.execute(statement, params).await
+    }
+
+    pub async fn query<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.connection.query(statement, params).await
+    }
+
+    /// Like [`Client::query`], but also returns the command tag, which holds
+    /// the row count for statements such as `UPDATE ... RETURNING`.
+    pub async fn query_with_tag<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<(Vec<Row>, CommandTag)>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.connection.query_with_tag(statement, params).await
+    }
+
+    /// Like [`Client::query`], but returns the rows one at a time as they
+    /// arrive instead of collecting them first.
+    pub async fn query_stream<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<RowStream<'_>>
+    where
+        T: ?Sized + ToStatement,
+    {
+        RowStream::start(&mut self.connection, statement, params, 0).await
+    }
+
+    /// Like [`Client::query_stream`], but only asks the server for
+    /// `max_rows` rows at a time, fetching the next batch once the previous
+    /// one has been read. This keeps a slow consumer from buffering the whole
+    /// result on the client.
+    ///
+    /// `max_rows` must be positive.
+    pub async fn query_stream_bounded<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+        max_rows: i32,
+    ) -> Result<RowStream<'_>>
+    where
+        T: ?Sized + ToStatement,
+    {
+        check_max_rows(max_rows)?;
+        RowStream::start(&mut self.connection, statement, params, max_rows).await
+    }
+
+    pub async fn batch_execute(&mut self, query: &str) -> Result<()> {
+        self.connection.batch_execute(query).await
+    }
+
+    /// Runs `query`, which may hold several statements separated by `;`,
+    /// with the simple query protocol. Returns a result for each statement,
+    /// with its rows in text form.
+    ///
+    /// Statements are run in an implicit transaction unless `query` manages
+    /// its own. Nothing after a failed statement is run, and only the error
+    /// is returned. Unlike [`Client::query`], parameters cannot be passed, so
+    /// this is for administrative commands and for servers, such as
+    /// PgBouncer's admin console, that do not support the extended protocol.
+    pub async fn simple_query(&mut self, query: &str) -> Result<Vec<SimpleQueryResult>> {
+        self.connection.simple_query(query).await
     }
 
-    pub async fn query(&mut self, query: &str) -> Result<Vec<Row>> {
-        self.connection.query(query).await
+    /// Starts a `COPY ... FROM STDIN` statement and returns a sink for its
+    /// data.
+    pub async fn copy_in(&mut self, query: &str) -> Result<CopyInSink<'_>> {
+        CopyInSink::start(&mut self.connection, query).await
+    }
+
+    /// Starts a `COPY ... TO STDOUT` statement and returns a stream of its
+    /// data.
+    pub async fn copy_out(&mut self, query: &str) -> Result<CopyOutStream<'_>> {
+        CopyOutStream::start(&mut self.connection, query).await
+    }
+
+    /// Starts a batch of queries that are sent together. See [`Pipeline`].
+    pub fn pipeline(&mut self) -> Pipeline<'_> {
+        Pipeline::new(&mut self.connection)
+    }
+
+    /// Starts a transaction with the server's default options. It is rolled
+    /// back if dropped without being committed.
+    pub async fn transaction(&mut self) -> Result<Transaction<'_>> {
+        Transaction::begin(&mut self.connection, "BEGIN").await
+    }
+
+    /// Returns a builder for a transaction with a non-default isolation
+    /// level, access mode or deferrability.
+    pub fn build_transaction(&mut self) -> TransactionBuilder<'_> {
+        TransactionBuilder::new(&mut self.connection)
+    }
+
+    pub(crate) fn defer_rollback_if_open(&mut self) {
+        self.connection.defer_rollback_if_open();
+    }
+}
+
+pub(crate) fn check_max_rows(max_rows: i32) -> Result<()> {
+    if max_rows <= 0 {
+        return Err(crate::error::Error::Parameter(format!(
+            "max_rows must be positive, got {}",
+            max_rows
+        )));
+    }
+    Ok(())
+}
+
+/// Parses a `server_version` such as `16.2 (Debian 16.2-1)`, `9.6.24` or
+/// `17beta1` into the `server_version_num` form.
+fn parse_server_version(version: &str) -> Option<u32> {
+    let version = version.split_whitespace().next()?;
+    let mut parts = version.split('.').map(|part| {
+        let digits = part
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(part.len());
+        part[..digits].parse::<u32>().ok()
+    });
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    if major >= 10 {
+        Some(major * 10000 + minor)
+    } else {
+        let patch = parts.next().flatten().unwrap_or(0);
+        Some(major * 10000 + minor * 100 + patch)
     }
 }
 
+/// Quotes `name` as an SQL identifier, doubling any `"` in it.
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
 #[derive(Debug, Clone)]
 pub struct Column {
     pub name: String,
     pub table_oid: u32,
     pub column_id: i16,
     pub type_oid: u32,
+    /// The type with OID `type_oid`, looked up on the server if it is not
+    /// built in.
+    pub type_: Type,
     pub type_len: i16,
     pub type_mod: i32,
     pub format: i16,
 }
 
-use postgres_types::{FromSql, Type};
+use postgres_types::{FromSql, ToSql, Type};
+
+/// Whether a connection is inside a transaction block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStatus {
+    Idle,
+    InTransaction,
+    /// In a transaction block that has failed. Every statement is rejected
+    /// until it is rolled back.
+    Failed,
+}
+
+/// The command tag from a `CommandComplete` message, which identifies the
+/// statement that finished and how many rows it touched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandTag {
+    /// `INSERT oid rows`. The OID is always 0 on servers without `WITH OIDS`.
+    Insert {
+        oid: u32,
+        rows: u64,
+    },
+    Update(u64),
+    Delete(u64),
+    /// Also reported by `CREATE TABLE AS` and `SELECT INTO`.
+    Select(u64),
+    Copy(u64),
+    Merge(u64),
+    Move(u64),
+    Fetch(u64),
+    /// A command without a row count, such as `CREATE TABLE` or `BEGIN`.
+    Other(String),
+}
+
+impl CommandTag {
+    pub fn parse(tag: &str) -> CommandTag {
+        let mut parts = tag.split(' ');
+        let command = parts.next().unwrap_or("");
+        let counts: Option<Vec<u64>> = parts.map(|p| p.parse().ok()).collect();
+        match (command, counts.as_deref()) {
+            ("INSERT", Some(&[oid, rows])) if oid <= u32::MAX as u64 => CommandTag::Insert {
+                oid: oid as u32,
+                rows,
+            },
+            ("UPDATE", Some(&[rows])) => CommandTag::Update(rows),
+            ("DELETE", Some(&[rows])) => CommandTag::Delete(rows),
+            ("SELECT", Some(&[rows])) => CommandTag::Select(rows),
+            ("COPY", Some(&[rows])) => CommandTag::Copy(rows),
+            ("MERGE", Some(&[rows])) => CommandTag::Merge(rows),
+            ("MOVE", Some(&[rows])) => CommandTag::Move(rows),
+            ("FETCH", Some(&[rows])) => CommandTag::Fetch(rows),
+            _ => CommandTag::Other(tag.to_string()),
+        }
+    }
+
+    /// Returns the number of rows processed, or 0 for commands without one.
+    pub fn rows(&self) -> u64 {
+        match *self {
+            CommandTag::Insert { rows, .. }
+            | CommandTag::Update(rows)
+            | CommandTag::Delete(rows)
+            | CommandTag::Select(rows)
+            | CommandTag::Copy(rows)
+            | CommandTag::Merge(rows)
+            | CommandTag::Move(rows)
+            | CommandTag::Fetch(rows) => rows,
+            CommandTag::Other(_) => 0,
+        }
+    }
+}
 
 pub struct Row {
     pub columns: Arc<Vec<Column>>,
@@ -46,17 +395,62 @@ pub struct Row {
 }
 
 impl Row {
-    pub fn get<'a, T: FromSql<'a>>(&'a self, index: usize) -> Result<T> {
-        let col = self.columns.get(index).ok_or_else(|| {
-            crate::error::Error::Parse(format!("Column index {} out of bounds", index))
-        })?;
-        let bytes = self.get_raw(index);
+    /// Returns the row's columns.
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Returns the number of values in the row.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Decodes the value of a column, given by position or by name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the column does not exist or its value cannot be converted
+    /// to `T`. [`Row::try_get`] returns an error instead.
+    pub fn get<'a, I, T>(&'a self, idx: I) -> T
+    where
+        I: RowIndex,
+        T: FromSql<'a>,
+    {
+        match self.try_get(&idx) {
+            Ok(value) => value,
+            Err(e) => panic!("error retrieving column {}: {}", idx, e),
+        }
+    }
 
-        let ty = Type::from_oid(col.type_oid).unwrap_or(Type::UNKNOWN);
+    /// Decodes the value of a column, given by position or by name.
+    ///
+    /// Fails if the column does not exist, if `T` does not accept the
+    /// column's type, or if the value cannot be decoded.
+    pub fn try_get<'a, I, T>(&'a self, idx: I) -> Result<T>
+    where
+        I: RowIndex,
+        T: FromSql<'a>,
+    {
+        let index = idx
+            .index(&self.columns)
+            .ok_or_else(|| Error::Parse(format!("column {} not found", idx)))?;
+        let ty = &self.columns[index].type_;
+        if !T::accepts(ty) {
+            return Err(Error::Parse(format!(
+                "cannot convert column {} of type {} to {}",
+                idx,
+                ty,
+                std::any::type_name::<T>()
+            )));
+        }
 
-        match bytes {
-            Some(b) => T::from_sql(&ty, b).map_err(|e| crate::error::Error::Parse(e.to_string())),
-            None => T::from_sql_null(&ty).map_err(|e| crate::error::Error::Parse(e.to_string())),
+        match self.get_raw(index) {
+            Some(b) => T::from_sql(ty, b).map_err(|e| Error::Parse(e.to_string())),
+            None => T::from_sql_null(ty).map_err(|e| Error::Parse(e.to_string())),
         }
     }
 
@@ -64,3 +458,39 @@ impl Row {
         self.data.get(index).and_then(|opt| opt.as_ref())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_server_version() {
+        assert_eq!(
+            parse_server_version("16.2 (Debian 16.2-1.pgdg120+2)"),
+            Some(160002)
+        );
+        assert_eq!(parse_server_version("9.6.24"), Some(90624));
+        assert_eq!(parse_server_version("17beta1"), Some(170000));
+        assert_eq!(parse_server_version("10.0"), Some(100000));
+        assert_eq!(parse_server_version("devel"), None);
+    }
+
+    #[test]
+    fn test_command_tag_parse() {
+        assert_eq!(
+            CommandTag::parse("INSERT 0 3"),
+            CommandTag::Insert { oid: 0, rows: 3 }
+        );
+        assert_eq!(CommandTag::parse("UPDATE 2"), CommandTag::Update(2));
+        assert_eq!(CommandTag::parse("DELETE 0"), CommandTag::Delete(0));
+        assert_eq!(CommandTag::parse("SELECT 100"), CommandTag::Select(100));
+        assert_eq!(CommandTag::parse("COPY 5"), CommandTag::Copy(5));
+        assert_eq!(CommandTag::parse("MERGE 1"), CommandTag::Merge(1));
+        assert_eq!(
+            CommandTag::parse("CREATE TABLE"),
+            CommandTag::Other("CREATE TABLE".into())
+        );
+        assert_eq!(CommandTag::parse("CREATE TABLE").rows(), 0);
+        assert_eq!(CommandTag::parse("INSERT 0 7").rows(), 7);
+    }
+}
@@ -1,56 +1,185 @@
 use crate::auth;
-use crate::client::{Column, Row};
+use crate::cancel::CancelToken;
+use crate::client::{Column, CommandTag, Row, TransactionStatus};
 use crate::codec::PostgresCodec;
-use crate::error::{Error, Result};
+use crate::config::{Config, NoticeHandler, TargetSessionAttrs};
+use crate::error::{DbError, Error, Result};
+use crate::notification::Notification;
+use crate::simple_query::{SimpleQueryResult, SimpleQueryRow};
+use crate::sqlstate::SqlState;
+use crate::statement::sealed::StatementRef;
+use crate::statement::{CloseQueue, Statement, ToStatement};
+use crate::stream::{self, Stream};
+use crate::tls::TlsConfig;
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use fallible_iterator::FallibleIterator;
 use monoio::io::sink::Sink;
-use monoio::io::stream::Stream;
-use monoio::net::TcpStream;
+use monoio::io::stream::Stream as _;
 use monoio_codec::Framed;
 use postgres_protocol::message::backend;
 use postgres_protocol::message::frontend;
-use std::collections::HashMap;
+use postgres_types::{Field, Kind, ToSql, Type};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use subtle::ConstantTimeEq;
 
-#[derive(Clone)]
-struct CachedStatement {
-    name: String,
-    columns: Arc<Vec<Column>>,
+/// Looks up a type that `postgres-types` does not know. Multiranges are
+/// treated as simple types.
+const TYPE_INFO_QUERY: &str = "\
+SELECT t.typname, t.typtype, t.typelem, r.rngsubtype, t.typbasetype, n.nspname, t.typrelid
+FROM pg_catalog.pg_type t
+LEFT OUTER JOIN pg_catalog.pg_range r ON r.rngtypid = t.oid
+INNER JOIN pg_catalog.pg_namespace n ON t.typnamespace = n.oid
+WHERE t.oid = $1";
+
+const TYPE_INFO_ENUM_QUERY: &str = "\
+SELECT enumlabel FROM pg_catalog.pg_enum WHERE enumtypid = $1 ORDER BY enumsortorder";
+
+const TYPE_INFO_COMPOSITE_QUERY: &str = "\
+SELECT attname, atttypid FROM pg_catalog.pg_attribute
+WHERE attrelid = $1 AND NOT attisdropped AND attnum > 0
+ORDER BY attnum";
+
+/// Numbers the statements prepared by every connection in the process, so a
+/// handle used on the wrong connection names no statement there.
+static NEXT_STATEMENT_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// How often [`Connection::next_notification_timeout`] polls for
+/// notifications over TLS.
+const NOTIFICATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A statement in the cache, with the tick of the cache's clock when it was
+/// last used.
+struct CacheEntry {
+    statement: Statement,
+    last_used: u64,
 }
 
 pub struct Connection {
-    framed: Framed<TcpStream, PostgresCodec>,
+    framed: Framed<Stream, PostgresCodec>,
+    cancel_token: CancelToken,
     write_buf: BytesMut,
-    statement_cache: HashMap<String, CachedStatement>,
-    next_stmt_id: usize,
+    /// Statements prepared implicitly for SQL text, keyed on that text.
+    statement_cache: HashMap<String, CacheEntry>,
+    cache_capacity: usize,
+    cache_clock: u64,
+    /// Types looked up by [`Connection::get_type`], keyed on their OIDs.
+    types: HashMap<u32, Type>,
+    /// Statements whose handles were dropped, closed before the next request.
+    close_queue: CloseQueue,
+    /// Statements queued by [`Connection::defer`], sent before the next
+    /// request.
+    deferred: Vec<String>,
+    /// `ReadyForQuery` messages still owed by requests already sent. Any left
+    /// when a request starts belong to responses that were abandoned part
+    /// way through, which are read and discarded first.
+    in_flight: usize,
+    /// Messages, such as `Sync`, that must be sent before abandoned responses
+    /// can finish.
+    drain_prelude: BytesMut,
+    statement_timeout: Option<Duration>,
+    /// The status from the last `ReadyForQuery`.
+    transaction_status: TransactionStatus,
+    /// Settings the server reports with `ParameterStatus`, kept up to date.
+    parameters: HashMap<String, String>,
+    /// Notifications received while reading responses to requests.
+    notifications: VecDeque<Notification>,
+    notice_handler: NoticeHandler,
+    /// Set once an I/O or protocol error leaves the connection unusable.
+    broken: bool,
+    /// Set while a read or write is in progress. It stays set only if the
+    /// future doing it was dropped, which loses buffered data, so the
+    /// connection is treated as broken.
+    io_pending: bool,
 }
 
 impl Connection {
-    pub async fn connect(
-        addr: &str,
-        user: &str,
-        password: Option<&str>,
-        database: Option<&str>,
+    /// Connects to the first of the configured hosts that accepts the
+    /// connection and matches the config's [`TargetSessionAttrs`]. If none
+    /// does, the error from the last one tried is returned.
+    pub async fn connect(config: &Config) -> Result<Self> {
+        let tls_config = config.resolved_tls()?;
+        let attrs = config.get_target_session_attrs();
+
+        // A server to settle for under `prefer-standby` if no standby is found.
+        let mut fallback = None;
+        let mut error = None;
+        for (host, port) in config.addrs()? {
+            let attempt = async {
+                let mut conn = Self::connect_host(config, host, port, tls_config.clone()).await?;
+                let matches = conn.matches_session_attrs(attrs).await?;
+                Ok::<_, Error>((conn, matches))
+            };
+            let res = match config.get_connect_timeout() {
+                Some(timeout) => monoio::time::timeout(timeout, attempt)
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(Error::Timeout(format!(
+                            "after {:?} connecting to {}:{}",
+                            timeout, host, port
+                        )))
+                    }),
+                None => attempt.await,
+            };
+            let e = match res {
+                Ok((conn, true)) => return Ok(conn),
+                Ok((conn, false)) => {
+                    if attrs == TargetSessionAttrs::PreferStandby && fallback.is_none() {
+                        fallback = Some(conn);
+                    }
+                    Error::Other(format!(
+                        "{}:{} does not match target_session_attrs {:?}",
+                        host, port, attrs
+                    ))
+                }
+                Err(e) => e,
+            };
+            tracing::debug!("skipping {}:{}: {}", host, port, e);
+            error = Some(e);
+        }
+
+        match fallback {
+            Some(conn) => Ok(conn),
+            None => Err(error.unwrap_or_else(|| Error::Config("no hosts given".into()))),
+        }
+    }
+
+    async fn connect_host(
+        config: &Config,
+        host: &str,
+        port: u16,
+        tls_config: TlsConfig,
     ) -> Result<Self> {
-        let stream = TcpStream::connect(addr).await?;
-        stream
-            .set_nodelay(true)
-            .map_err(|e| Error::Other(e.to_string()))?;
+        let user = config
+            .get_user()
+            .ok_or_else(|| Error::Config("user is required".into()))?;
+        let password = config.get_password();
+
+        let stream = stream::connect(host, port, &tls_config, config.keepalive()).await?;
         let mut framed = Framed::new(stream, PostgresCodec);
 
         // 1. Send Startup Message
         let mut params = vec![("user", user)];
-        if let Some(db) = database {
+        if let Some(db) = config.get_dbname() {
             params.push(("database", db));
         }
+        if let Some(name) = config.get_application_name() {
+            params.push(("application_name", name));
+        }
+        if let Some(options) = config.get_options() {
+            params.push(("options", options));
+        }
         params.push(("client_encoding", "UTF8"));
+        for (name, value) in config.get_params() {
+            params.push((name, value));
+        }
 
         let mut buf = BytesMut::new();
-        frontend::startup_message(params.into_iter(), &mut buf)
-            .map_err(|e| Error::Protocol(e.to_string()))?;
+        frontend::startup_message(params, &mut buf).map_err(|e| Error::Protocol(e.to_string()))?;
 
         // This is a bit tricky since Framed's encoder expects BytesMut
         // But we want to send the raw bytes.
@@ -75,7 +204,8 @@ impl Connection {
             .flush()
             .await
             .map_err(|e| Error::Other(e.to_string()))?;
-        let mut parameters = std::collections::HashMap::new();
+        let mut parameters = HashMap::new();
+        let mut key = None;
 
         // 2. Handle Authentication
         let mut scram_state: Option<(auth::ScramClient, [u8; 32])> = None;
@@ -169,167 +299,565 @@ impl Connection {
                     }
                 }
                 backend::Message::ErrorResponse(body) => {
-                    let mut fields = body.fields();
-                    let first = FallibleIterator::next(&mut fields)
-                        .map_err(|e: std::io::Error| Error::Protocol(e.to_string()))?;
-                    let msg = first
-                        .map(|f: postgres_protocol::message::backend::ErrorField| {
-                            String::from_utf8_lossy(f.value_bytes()).to_string()
-                        })
-                        .unwrap_or_else(|| "Unknown error".to_string());
-                    return Err(Error::Authentication(msg));
+                    return Err(error_response(body)?);
                 }
                 backend::Message::ParameterStatus(body) => {
-                    let name = body
-                        .name()
-                        .map_err(|e| Error::Protocol(e.to_string()))?
-                        .to_string();
-                    let value = body
-                        .value()
-                        .map_err(|e| Error::Protocol(e.to_string()))?
-                        .to_string();
+                    let (name, value) = parameter_status(body)?;
                     parameters.insert(name, value);
                 }
                 backend::Message::ReadyForQuery(_) => break,
-                backend::Message::BackendKeyData(_) => {}
+                backend::Message::BackendKeyData(body) => {
+                    key = Some((body.process_id(), body.secret_key()));
+                }
                 _ => {}
             }
         }
 
         Ok(Self {
             framed,
+            cancel_token: CancelToken::new(host, port, tls_config, key),
             write_buf: BytesMut::with_capacity(4096),
             statement_cache: HashMap::new(),
-            next_stmt_id: 1,
+            cache_capacity: config.get_statement_cache_capacity(),
+            cache_clock: 0,
+            types: HashMap::new(),
+            close_queue: CloseQueue::default(),
+            deferred: Vec::new(),
+            in_flight: 0,
+            drain_prelude: BytesMut::new(),
+            statement_timeout: config.get_statement_timeout(),
+            transaction_status: TransactionStatus::Idle,
+            parameters,
+            notifications: VecDeque::new(),
+            notice_handler: config.get_notice_handler().clone(),
+            broken: false,
+            io_pending: false,
         })
     }
 
-    pub async fn query(&mut self, query: &str) -> Result<Vec<Row>> {
-        self.write_buf.clear();
-
-        let cached = self.statement_cache.get(query).cloned();
-        let name = if let Some(ref stmt) = cached {
-            stmt.name.clone()
-        } else {
-            let n = format!("s{}", self.next_stmt_id);
-            self.next_stmt_id += 1;
-            frontend::parse(&n, query, std::iter::empty(), &mut self.write_buf)
-                .map_err(|e| Error::Protocol(e.to_string()))?;
-            n
-        };
+    /// Whether the server is one that `attrs` accepts. Servers that report
+    /// `in_hot_standby` and `default_transaction_read_only` at startup, as
+    /// PostgreSQL 14 and later do, are checked without a query.
+    async fn matches_session_attrs(&mut self, attrs: TargetSessionAttrs) -> Result<bool> {
+        Ok(match attrs {
+            TargetSessionAttrs::Any => true,
+            TargetSessionAttrs::ReadWrite => !self.is_read_only().await?,
+            TargetSessionAttrs::ReadOnly => self.is_read_only().await?,
+            TargetSessionAttrs::Primary => !self.is_hot_standby().await?,
+            TargetSessionAttrs::Standby | TargetSessionAttrs::PreferStandby => {
+                self.is_hot_standby().await?
+            }
+        })
+    }
 
-        // Note: formats = format codes for bound parameters. We have no parameters.
-        // result_formats = format codes for results. We use 1 (binary).
-        frontend::bind(
-            "",
-            &name,
-            std::iter::empty::<i16>(),
-            std::iter::empty::<i32>(),
-            |_: i32,
-             _: &mut BytesMut|
-             -> std::result::Result<
-                postgres_protocol::IsNull,
-                Box<dyn std::error::Error + Sync + Send>,
-            > { Ok(postgres_protocol::IsNull::Yes) },
-            std::iter::once(1),
-            &mut self.write_buf,
-        )
-        .map_err(|_| Error::Protocol("Bind error".to_string()))?;
+    async fn is_read_only(&mut self) -> Result<bool> {
+        if let (Some(standby), Some(read_only)) = (
+            self.parameter("in_hot_standby"),
+            self.parameter("default_transaction_read_only"),
+        ) {
+            return Ok(standby == "on" || read_only == "on");
+        }
+        let value = self
+            .simple_query_scalar("SHOW transaction_read_only")
+            .await?;
+        Ok(value.as_deref() == Some("on"))
+    }
 
-        if cached.is_none() {
-            frontend::describe(b'P', "", &mut self.write_buf).map_err(|e| Error::Protocol(e.to_string()))?;
+    async fn is_hot_standby(&mut self) -> Result<bool> {
+        if let Some(standby) = self.parameter("in_hot_standby") {
+            return Ok(standby == "on");
         }
+        let value = self
+            .simple_query_scalar("SELECT pg_catalog.pg_is_in_recovery()")
+            .await?;
+        Ok(value.as_deref() == Some("t"))
+    }
 
-        frontend::execute("", 0, &mut self.write_buf).map_err(|e| Error::Protocol(e.to_string()))?;
-        frontend::sync(&mut self.write_buf);
-        self.framed
-            .send(self.write_buf.split())
+    pub async fn query<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.query_with_tag(statement, params)
             .await
-            .map_err(|e| Error::Other(e.to_string()))?;
-        self.framed
-            .flush()
+            .map(|(rows, _)| rows)
+    }
+
+    /// Like [`Connection::query`], but also returns the `CommandComplete` tag
+    /// reported by the server.
+    pub async fn query_with_tag<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<(Vec<Row>, CommandTag)>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.run_statement(statement, params, true).await
+    }
+
+    /// Executes a statement and returns the number of rows it affected.
+    pub async fn execute<T>(&mut self, statement: &T, params: &[&(dyn ToSql + Sync)]) -> Result<u64>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.run_statement(statement, params, false)
             .await
-            .map_err(|e| Error::Other(e.to_string()))?;
+            .map(|(_, tag)| tag.rows())
+    }
 
+    /// Binds and executes `statement` under the statement timeout, keeping
+    /// the returned rows only if `collect` is set.
+    async fn run_statement<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+        collect: bool,
+    ) -> Result<(Vec<Row>, CommandTag)>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let timeout = self.statement_timeout_token();
+        let res =
+            with_statement_timeout(timeout, self.execute_statement(statement, params, collect))
+                .await;
+        res.unwrap_or_else(|| Err(self.abandon_timed_out()))
+    }
+
+    /// Binds and executes `statement` with no timeout.
+    ///
+    /// If a cached statement has gone stale because the schema changed under
+    /// it, it is prepared again and run once more, unless that happened
+    /// inside a transaction block, which the error has aborted.
+    async fn execute_statement<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+        collect: bool,
+    ) -> Result<(Vec<Row>, CommandTag)>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.run_deferred().await?;
+        let mut stmt = self.resolve(statement).await?;
+        let mut retried = false;
+        loop {
+            self.bind_execute(&stmt, params, 0).await?;
+            match self.read_response(&stmt, collect).await? {
+                Err(e)
+                    if !retried
+                        && is_stale_statement(&e)
+                        && self.transaction_status == TransactionStatus::Idle
+                        && matches!(statement.statement_ref(), StatementRef::Query(_)) =>
+                {
+                    retried = true;
+                    stmt = self.resolve(statement).await?;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Reads the response to one `Bind`/`Execute`/`Sync` group for `stmt`, up
+    /// to and including `ReadyForQuery`. A server error is returned in the
+    /// inner result, since the connection is still usable afterwards.
+    pub(crate) async fn read_response(
+        &mut self,
+        stmt: &Statement,
+        collect: bool,
+    ) -> Result<Result<(Vec<Row>, CommandTag)>> {
+        let mut rows = Vec::new();
+        let mut tag = CommandTag::Other(String::new());
+        let mut error = None;
+        loop {
+            let (msg, raw) = self.recv().await?;
+            match msg {
+                backend::Message::DataRow(_) if collect => {
+                    rows.push(data_row(&raw, stmt.column_list())?);
+                }
+                backend::Message::CommandComplete(body) => {
+                    tag = command_tag(&body)?;
+                }
+                backend::Message::ReadyForQuery(_) => break,
+                backend::Message::ErrorResponse(body) => {
+                    let e = error_response(body)?;
+                    self.evict_if_stale(stmt, &e);
+                    error = Some(e);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(match error {
+            Some(e) => Err(e),
+            None => Ok((rows, tag)),
+        })
+    }
+
+    /// Runs one or more `;`-separated statements through the simple query
+    /// protocol. No parameters can be bound and any returned rows are discarded.
+    pub async fn batch_execute(&mut self, query: &str) -> Result<()> {
+        let timeout = self.statement_timeout_token();
+        let res = with_statement_timeout(timeout, async {
+            self.run_deferred().await?;
+            self.simple_execute(query).await
+        })
+        .await;
+        res.unwrap_or_else(|| Err(self.abandon_timed_out()))
+    }
+
+    /// Runs `query`, which may hold several statements, with the simple query
+    /// protocol and returns the result of each one that finished. Statements
+    /// after an error are not run, and the error is returned instead.
+    pub async fn simple_query(&mut self, query: &str) -> Result<Vec<SimpleQueryResult>> {
+        let timeout = self.statement_timeout_token();
+        let res = with_statement_timeout(timeout, self.run_simple_query(query)).await;
+        res.unwrap_or_else(|| Err(self.abandon_timed_out()))
+    }
+
+    async fn run_simple_query(&mut self, query: &str) -> Result<Vec<SimpleQueryResult>> {
+        self.run_deferred().await?;
+        self.write_buf.clear();
+        frontend::query(query, &mut self.write_buf).map_err(|e| Error::Protocol(e.to_string()))?;
+        self.flush_write_buf().await?;
+
+        let mut results = Vec::new();
+        let mut columns = Arc::new(Vec::new());
         let mut rows = Vec::new();
-        let mut columns = if let Some(ref stmt) = cached {
-            stmt.columns.clone()
-        } else {
-            Arc::new(Vec::new())
-        };
         let mut error = None;
         loop {
-            let (msg, raw) = self.framed.next().await.ok_or(Error::Closed)??;
+            let (msg, raw) = self.recv().await?;
             match msg {
                 backend::Message::RowDescription(body) => {
-                    let mut cols = Vec::new();
-                    let mut fields = body.fields();
-                    while let Some(field) = FallibleIterator::next(&mut fields)
-                        .map_err(|e: std::io::Error| Error::Protocol(e.to_string()))?
-                    {
-                        cols.push(Column {
-                            name: field.name().to_string(),
-                            table_oid: field.table_oid(),
-                            column_id: field.column_id(),
-                            type_oid: field.type_oid(),
-                            type_len: 0, // In this version, type_len might be missing or renamed
-                            type_mod: field.type_modifier(),
-                            format: field.format(),
-                        });
+                    let mut cols = parse_columns(body)?;
+                    for col in &mut cols {
+                        col.format = 0;
                     }
                     columns = Arc::new(cols);
-                    self.statement_cache.insert(
-                        query.to_string(),
-                        CachedStatement {
-                            name: name.clone(),
-                            columns: columns.clone(),
-                        },
-                    );
-                }
-                backend::Message::NoData => {
-                    self.statement_cache.insert(
-                        query.to_string(),
-                        CachedStatement {
-                            name: name.clone(),
-                            columns: columns.clone(),
-                        },
-                    );
                 }
                 backend::Message::DataRow(_) => {
-                    let col_count = columns.len();
-                    let mut data = Vec::with_capacity(col_count);
-                    let mut cursor = 7; // tag(1) + len(4) + col_count(2)
-                    for _ in 0..col_count {
-                        let len = i32::from_be_bytes([raw[cursor], raw[cursor + 1], raw[cursor + 2], raw[cursor + 3]]);
-                        cursor += 4;
-                        if len == -1 {
-                            data.push(None);
-                        } else {
-                            let len = len as usize;
-                            data.push(Some(raw.slice(cursor..cursor + len)));
-                            cursor += len;
-                        }
+                    let Row { columns, data } = data_row(&raw, &columns)?;
+                    rows.push(SimpleQueryRow { columns, data });
+                }
+                backend::Message::CommandComplete(body) => {
+                    results.push(SimpleQueryResult {
+                        columns: std::mem::take(&mut columns),
+                        rows: std::mem::take(&mut rows),
+                        tag: command_tag(&body)?,
+                    });
+                }
+                backend::Message::ReadyForQuery(_) => break,
+                backend::Message::ErrorResponse(body) => {
+                    error = Some(error_response(body)?);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(e) = error {
+            return Err(e);
+        }
+        Ok(results)
+    }
+
+    /// Starts a query whose rows are read one at a time by a
+    /// [`RowStream`](crate::RowStream). See [`Connection::bind_execute`] for
+    /// the meaning of `max_rows`.
+    pub(crate) async fn start_query<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+        max_rows: i32,
+    ) -> Result<Statement>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.run_deferred().await?;
+        let stmt = self.resolve(statement).await?;
+        self.bind_execute(&stmt, params, max_rows).await?;
+        Ok(stmt)
+    }
+
+    /// Asks for the next `max_rows` rows of a suspended portal.
+    pub(crate) async fn continue_portal(&mut self, max_rows: i32) -> Result<()> {
+        self.write_buf.clear();
+        frontend::execute("", max_rows, &mut self.write_buf)
+            .map_err(|e| Error::Protocol(e.to_string()))?;
+        frontend::flush(&mut self.write_buf);
+        self.flush_write_buf().await
+    }
+
+    /// Sends `Sync`, closing a portal left open by a bounded query.
+    pub(crate) async fn sync(&mut self) -> Result<()> {
+        self.write_buf.clear();
+        frontend::sync(&mut self.write_buf);
+        self.flush_write_buf().await
+    }
+
+    /// Reads the next message belonging to the current request. Notifications
+    /// that arrive in the meantime are buffered.
+    pub(crate) async fn recv(&mut self) -> Result<(backend::Message, Bytes)> {
+        loop {
+            match self.read_message().await? {
+                (backend::Message::NotificationResponse(body), _) => {
+                    let notification = notification(body)?;
+                    self.notifications.push_back(notification);
+                }
+                msg => return Ok(msg),
+            }
+        }
+    }
+
+    /// Returns the next notification, waiting for one if none are buffered.
+    pub(crate) async fn next_notification(&mut self) -> Result<Notification> {
+        if let Some(notification) = self.notifications.pop_front() {
+            return Ok(notification);
+        }
+        self.run_deferred().await?;
+        loop {
+            if let Some(notification) = self.notifications.pop_front() {
+                return Ok(notification);
+            }
+            if let (backend::Message::NotificationResponse(body), _) = self.read_message().await? {
+                return notification(body);
+            }
+        }
+    }
+
+    /// Like [`Connection::next_notification`], but returns `None` if none
+    /// arrives within `timeout`. Nothing is read until the socket is readable,
+    /// so giving up leaves the connection usable. Over TLS, where readiness
+    /// cannot be trusted, the server is polled with empty queries instead.
+    pub(crate) async fn next_notification_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<Notification>> {
+        if let Some(notification) = self.notifications.pop_front() {
+            return Ok(Some(notification));
+        }
+        self.run_deferred().await?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(notification) = self.notifications.pop_front() {
+                return Ok(Some(notification));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            if self.framed.read_buffer().is_empty() {
+                match monoio::time::timeout(remaining, self.framed.get_ref().readable()).await {
+                    Err(_) => return Ok(None),
+                    Ok(Some(res)) => res?,
+                    Ok(None) => {
+                        monoio::time::sleep(remaining.min(NOTIFICATION_POLL_INTERVAL)).await;
+                        self.simple_execute("").await?;
+                        continue;
                     }
+                }
+            }
+            if let Some((backend::Message::NotificationResponse(body), _)) =
+                self.read_next().await?
+            {
+                return notification(body).map(Some);
+            }
+        }
+    }
 
-                    rows.push(Row {
-                        columns: columns.clone(),
-                        data,
-                    });
+    pub(crate) fn take_notification(&mut self) -> Option<Notification> {
+        self.notifications.pop_front()
+    }
+
+    /// Reads the next message from the server, passing notices to the notice
+    /// handler and recording parameter changes along the way.
+    async fn read_message(&mut self) -> Result<(backend::Message, Bytes)> {
+        loop {
+            if let Some(msg) = self.read_next().await? {
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Reads one message from the server. Returns `None` if it was a notice
+    /// or a parameter change, which are handled here.
+    async fn read_next(&mut self) -> Result<Option<(backend::Message, Bytes)>> {
+        self.check_open()?;
+        self.io_pending = true;
+        let res = self
+            .framed
+            .next()
+            .await
+            .ok_or(Error::Closed)
+            .and_then(|r| r);
+        self.io_pending = false;
+        match res {
+            Ok((backend::Message::NoticeResponse(body), _)) => {
+                let notice = DbError::parse(&mut body.fields())?;
+                (self.notice_handler)(&notice);
+                Ok(None)
+            }
+            Ok((backend::Message::ParameterStatus(body), _)) => {
+                let (name, value) = parameter_status(body)?;
+                self.parameters.insert(name, value);
+                Ok(None)
+            }
+            Ok((backend::Message::ReadyForQuery(body), raw)) => {
+                self.in_flight = self.in_flight.saturating_sub(1);
+                self.transaction_status = match body.status() {
+                    b'T' => TransactionStatus::InTransaction,
+                    b'E' => TransactionStatus::Failed,
+                    _ => TransactionStatus::Idle,
+                };
+                Ok(Some((backend::Message::ReadyForQuery(body), raw)))
+            }
+            Ok(msg) => Ok(Some(msg)),
+            Err(e) => {
+                self.broken = true;
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the value of a setting the server reports to clients, such as
+    /// `server_version`, `TimeZone` or `application_name`.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters.get(name).map(String::as_str)
+    }
+
+    /// Returns whether the connection is in a transaction block, as of the
+    /// end of the last request.
+    pub fn transaction_status(&self) -> TransactionStatus {
+        self.transaction_status
+    }
+
+    /// Replaces the function that receives notices from the server.
+    pub fn set_notice_handler(&mut self, handler: NoticeHandler) {
+        self.notice_handler = handler;
+    }
+
+    /// Returns a token for cancelling the query running on this connection.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel_token.clone()
+    }
+
+    /// Replaces the statement timeout set by
+    /// [`Config::statement_timeout`], or removes it if `None`.
+    pub fn set_statement_timeout(&mut self, timeout: Option<Duration>) {
+        self.statement_timeout = timeout;
+    }
+
+    /// Returns the statement timeout along with the token to cancel with
+    /// when it expires.
+    fn statement_timeout_token(&self) -> Option<(Duration, CancelToken)> {
+        self.statement_timeout
+            .map(|timeout| (timeout, self.cancel_token.clone()))
+    }
+
+    /// Marks the connection broken after a timed-out request was abandoned
+    /// partway through its response, and returns the error for it.
+    fn abandon_timed_out(&mut self) -> Error {
+        self.broken = true;
+        Error::Timeout(format!(
+            "after {:?} waiting for the query, which could not be cancelled",
+            self.statement_timeout.unwrap_or_default()
+        ))
+    }
+
+    /// Returns whether the connection can no longer be used, because the
+    /// server closed it, an I/O or protocol error left it in an unknown
+    /// state, or a request was dropped while reading from or writing to the
+    /// socket. Requests on a closed connection fail with [`Error::Broken`].
+    pub fn is_closed(&self) -> bool {
+        self.broken || self.io_pending
+    }
+
+    fn check_open(&self) -> Result<()> {
+        if self.is_closed() {
+            return Err(Error::Broken);
+        }
+        Ok(())
+    }
+
+    /// Sends a `COPY ... FROM STDIN` statement and waits until the server is
+    /// ready for data.
+    pub(crate) async fn copy_in_start(&mut self, query: &str) -> Result<()> {
+        self.run_deferred().await?;
+        self.write_buf.clear();
+        frontend::query(query, &mut self.write_buf).map_err(|e| Error::Protocol(e.to_string()))?;
+        self.flush_write_buf().await?;
+
+        let mut error = None;
+        loop {
+            let (msg, _raw) = self.recv().await?;
+            match msg {
+                backend::Message::CopyInResponse(_) => return Ok(()),
+                backend::Message::ErrorResponse(body) => {
+                    error = Some(error_response(body)?);
                 }
-                backend::Message::CommandComplete(_body) => {}
                 backend::Message::ReadyForQuery(_) => break,
+                _ => {}
+            }
+        }
+
+        Err(error
+            .unwrap_or_else(|| Error::Protocol("statement did not start a COPY FROM STDIN".into())))
+    }
+
+    /// Sends a `COPY ... TO STDOUT` statement and waits until the server
+    /// starts sending data.
+    pub(crate) async fn copy_out_start(&mut self, query: &str) -> Result<()> {
+        self.run_deferred().await?;
+        self.write_buf.clear();
+        frontend::query(query, &mut self.write_buf).map_err(|e| Error::Protocol(e.to_string()))?;
+        self.flush_write_buf().await?;
+
+        let mut error = None;
+        loop {
+            let (msg, _raw) = self.recv().await?;
+            match msg {
+                backend::Message::CopyOutResponse(_) => return Ok(()),
                 backend::Message::ErrorResponse(body) => {
-                    let mut fields = body.fields();
-                    let first = FallibleIterator::next(&mut fields)
-                        .map_err(|e: std::io::Error| Error::Protocol(e.to_string()))?;
-                    let msg = first
-                        .map(|f: postgres_protocol::message::backend::ErrorField| {
-                            String::from_utf8_lossy(f.value_bytes()).into_owned()
-                        })
-                        .unwrap_or_else(|| "Unknown error".to_string());
-                    error = Some(Error::Protocol(msg));
+                    error = Some(error_response(body)?);
+                }
+                backend::Message::ReadyForQuery(_) => break,
+                _ => {}
+            }
+        }
+
+        Err(error
+            .unwrap_or_else(|| Error::Protocol("statement did not start a COPY TO STDOUT".into())))
+    }
+
+    /// Sends one `CopyData` message.
+    pub(crate) async fn copy_data(&mut self, data: Bytes) -> Result<()> {
+        self.write_buf.clear();
+        frontend::CopyData::new(data)
+            .map_err(|e| Error::Protocol(e.to_string()))?
+            .write(&mut self.write_buf);
+        self.flush_write_buf().await
+    }
+
+    /// Ends a copy with `CopyDone` and returns the number of rows loaded.
+    pub(crate) async fn copy_in_finish(&mut self) -> Result<u64> {
+        self.write_buf.clear();
+        frontend::copy_done(&mut self.write_buf);
+        self.flush_write_buf().await?;
+
+        let mut rows = 0;
+        let mut error = None;
+        loop {
+            let (msg, _raw) = self.recv().await?;
+            match msg {
+                backend::Message::CommandComplete(body) => {
+                    rows = command_tag(&body)?.rows();
+                }
+                backend::Message::ErrorResponse(body) => {
+                    error = Some(error_response(body)?);
                 }
-                backend::Message::ParseComplete | backend::Message::BindComplete => {}
+                backend::Message::ReadyForQuery(_) => break,
                 _ => {}
             }
         }
@@ -340,33 +868,83 @@ impl Connection {
         Ok(rows)
     }
 
-    pub async fn execute(&mut self, query: &str) -> Result<()> {
+    /// Aborts a copy with `CopyFail`. The server discards everything sent so
+    /// far and answers with an error, which is expected and not returned.
+    pub(crate) async fn copy_in_fail(&mut self, message: &str) -> Result<()> {
+        self.write_buf.clear();
+        frontend::copy_fail(message, &mut self.write_buf)
+            .map_err(|e| Error::Protocol(e.to_string()))?;
+        self.flush_write_buf().await?;
+
+        loop {
+            if let (backend::Message::ReadyForQuery(_), _) = self.recv().await? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Queues a simple-protocol statement to be sent before the next request.
+    /// This is how `Drop` impls, which cannot await, clean up server state.
+    pub(crate) fn defer(&mut self, sql: String) {
+        self.deferred.push(sql);
+    }
+
+    /// Queues a `ROLLBACK` if the connection was left in a transaction block
+    /// that nothing is already set to end, such as one opened with a plain
+    /// `BEGIN` and never finished.
+    pub(crate) fn defer_rollback_if_open(&mut self) {
+        if self.transaction_status != TransactionStatus::Idle && self.deferred.is_empty() {
+            self.defer("ROLLBACK".to_string());
+        }
+    }
+
+    /// Returns the buffer for messages that must be sent before an abandoned
+    /// response can finish, such as the `Sync` of a suspended portal. They
+    /// are sent before the next request.
+    pub(crate) fn defer_drain(&mut self) -> &mut BytesMut {
+        &mut self.drain_prelude
+    }
+
+    /// Readies the connection for a new request: closes dropped statements,
+    /// discards what is left of abandoned responses and runs deferred
+    /// statements. Fails fast if the connection is closed.
+    async fn run_deferred(&mut self) -> Result<()> {
+        self.check_open()?;
+        let closed = std::mem::take(&mut *self.close_queue.borrow_mut());
+        if !closed.is_empty() {
+            for name in &closed {
+                frontend::close(b'S', name, &mut self.drain_prelude)
+                    .map_err(|e| Error::Protocol(e.to_string()))?;
+            }
+            frontend::sync(&mut self.drain_prelude);
+        }
+        if !self.drain_prelude.is_empty() {
+            self.write_buf.clear();
+            self.write_buf.unsplit(self.drain_prelude.split());
+            self.flush_write_buf().await?;
+        }
+        while self.in_flight > 0 {
+            self.recv().await?;
+        }
+        while !self.deferred.is_empty() {
+            let sql = self.deferred.remove(0);
+            self.simple_execute(&sql).await?;
+        }
+        Ok(())
+    }
+
+    async fn simple_execute(&mut self, query: &str) -> Result<()> {
         self.write_buf.clear();
         frontend::query(query, &mut self.write_buf).map_err(|e| Error::Protocol(e.to_string()))?;
-        self.framed
-            .send(self.write_buf.split())
-            .await
-            .map_err(|e| Error::Other(e.to_string()))?;
-        self.framed
-            .flush()
-            .await
-            .map_err(|e| Error::Other(e.to_string()))?;
+        self.flush_write_buf().await?;
 
         let mut error = None;
         loop {
-            let (msg, _raw) = self.framed.next().await.ok_or(Error::Closed)??;
+            let (msg, _raw) = self.recv().await?;
             match msg {
                 backend::Message::ReadyForQuery(_) => break,
                 backend::Message::ErrorResponse(body) => {
-                    let mut fields = body.fields();
-                    let first = FallibleIterator::next(&mut fields)
-                        .map_err(|e: std::io::Error| Error::Protocol(e.to_string()))?;
-                    let msg = first
-                        .map(|f: postgres_protocol::message::backend::ErrorField| {
-                            String::from_utf8_lossy(f.value_bytes()).into_owned()
-                        })
-                        .unwrap_or_else(|| "Unknown error".to_string());
-                    error = Some(Error::Protocol(msg));
+                    error = Some(error_response(body)?);
                 }
                 _ => {}
             }
@@ -378,36 +956,197 @@ impl Connection {
         Ok(())
     }
 
-    pub async fn prepare(&mut self, name: &str, query: &str) -> Result<()> {
+    /// Runs `query` with the simple query protocol and returns the first
+    /// column of its first row, as text.
+    async fn simple_query_scalar(&mut self, query: &str) -> Result<Option<String>> {
         self.write_buf.clear();
-        frontend::parse(name, query, std::iter::empty(), &mut self.write_buf)
+        frontend::query(query, &mut self.write_buf).map_err(|e| Error::Protocol(e.to_string()))?;
+        self.flush_write_buf().await?;
+
+        let mut value = None;
+        let mut error = None;
+        loop {
+            let (msg, _raw) = self.recv().await?;
+            match msg {
+                backend::Message::DataRow(body) if value.is_none() => {
+                    let range = body
+                        .ranges()
+                        .next()
+                        .map_err(|e| Error::Protocol(e.to_string()))?;
+                    if let Some(Some(range)) = range {
+                        value = Some(String::from_utf8_lossy(&body.buffer()[range]).into_owned());
+                    }
+                }
+                backend::Message::ReadyForQuery(_) => break,
+                backend::Message::ErrorResponse(body) => {
+                    error = Some(error_response(body)?);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(e) = error {
+            return Err(e);
+        }
+        Ok(value)
+    }
+
+    /// Returns the statement to execute, preparing SQL text through the cache.
+    async fn resolve<T>(&mut self, statement: &T) -> Result<Statement>
+    where
+        T: ?Sized + ToStatement,
+    {
+        match statement.statement_ref() {
+            StatementRef::Query(query) => self.prepare_cached(query).await,
+            StatementRef::Statement(stmt) if stmt.belongs_to(&self.close_queue) => Ok(stmt.clone()),
+            StatementRef::Statement(_) => Err(Error::Parameter(
+                "statement was prepared on a different connection".into(),
+            )),
+        }
+    }
+
+    /// Returns the cached statement for `query`, parsing and describing it on
+    /// the server first if this is the first time it is seen. With the cache
+    /// disabled, `query` is described as the unnamed statement instead.
+    async fn prepare_cached(&mut self, query: &str) -> Result<Statement> {
+        if self.cache_capacity == 0 {
+            return self.prepare_statement(query, &[], false).await;
+        }
+
+        self.cache_clock += 1;
+        if let Some(entry) = self.statement_cache.get_mut(query) {
+            entry.last_used = self.cache_clock;
+            return Ok(entry.statement.clone());
+        }
+
+        let stmt = self.prepare_statement(query, &[], true).await?;
+        if self.statement_cache.len() >= self.cache_capacity {
+            let oldest = self
+                .statement_cache
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(query, _)| query.clone());
+            // Dropping the entry queues a `Close` for its statement.
+            if let Some(oldest) = oldest {
+                self.statement_cache.remove(&oldest);
+            }
+        }
+        self.statement_cache.insert(
+            query.to_string(),
+            CacheEntry {
+                statement: stmt.clone(),
+                last_used: self.cache_clock,
+            },
+        );
+        Ok(stmt)
+    }
+
+    /// Removes `stmt` from the cache if `error` means it is no longer valid,
+    /// so the next use of its SQL prepares it again.
+    pub(crate) fn evict_if_stale(&mut self, stmt: &Statement, error: &Error) {
+        if is_stale_statement(error)
+            && self
+                .statement_cache
+                .get(stmt.query())
+                .is_some_and(|entry| entry.statement == *stmt)
+        {
+            self.statement_cache.remove(stmt.query());
+        }
+    }
+
+    /// Drops every cached statement. They are closed on the server before the
+    /// next request. Types looked up on the server are forgotten too, so
+    /// changes such as new enum labels are picked up.
+    pub fn clear_statement_cache(&mut self) {
+        self.statement_cache.clear();
+        self.types.clear();
+    }
+
+    /// Parses and describes `query`, as a new named statement if `named` is
+    /// set and as the unnamed statement otherwise. Parameters without an entry
+    /// in `types` have their types inferred by the server.
+    async fn prepare_statement(
+        &mut self,
+        query: &str,
+        types: &[Type],
+        named: bool,
+    ) -> Result<Statement> {
+        let name = if named {
+            format!("s{}", NEXT_STATEMENT_ID.fetch_add(1, Ordering::Relaxed))
+        } else {
+            String::new()
+        };
+
+        let (param_oids, mut columns) = self.describe_statement(&name, query, types).await?;
+        let res = async {
+            let mut params = Vec::with_capacity(param_oids.len());
+            for oid in param_oids {
+                params.push(self.get_type(oid).await?);
+            }
+            for column in &mut columns {
+                column.type_ = self.get_type(column.type_oid).await?;
+            }
+            Ok(params)
+        }
+        .await;
+        let params = match res {
+            Ok(params) => params,
+            Err(e) => {
+                if named {
+                    self.close_queue.borrow_mut().push(name);
+                }
+                return Err(e);
+            }
+        };
+
+        Ok(if named {
+            Statement::new(name, query, params, columns, &self.close_queue)
+        } else {
+            Statement::unnamed(query, params, columns)
+        })
+    }
+
+    /// Sends `Parse` and `Describe` for `query` and returns the OIDs of its
+    /// parameters and its columns. Column types are only filled in for types
+    /// `postgres-types` knows.
+    async fn describe_statement(
+        &mut self,
+        name: &str,
+        query: &str,
+        types: &[Type],
+    ) -> Result<(Vec<u32>, Vec<Column>)> {
+        self.write_buf.clear();
+        frontend::parse(
+            name,
+            query,
+            types.iter().map(Type::oid),
+            &mut self.write_buf,
+        )
+        .map_err(|e| Error::Protocol(e.to_string()))?;
+        frontend::describe(b'S', name, &mut self.write_buf)
             .map_err(|e| Error::Protocol(e.to_string()))?;
         frontend::sync(&mut self.write_buf);
-        self.framed
-            .send(self.write_buf.split())
-            .await
-            .map_err(|e| Error::Other(e.to_string()))?;
-        self.framed
-            .flush()
-            .await
-            .map_err(|e| Error::Other(e.to_string()))?;
+        self.flush_write_buf().await?;
 
+        let mut params = Vec::new();
+        let mut columns = Vec::new();
         let mut error = None;
         loop {
-            let (msg, _raw) = self.framed.next().await.ok_or(Error::Closed)??;
+            let (msg, _raw) = self.recv().await?;
             match msg {
-                backend::Message::ParseComplete => {}
+                backend::Message::ParseComplete | backend::Message::NoData => {}
+                backend::Message::ParameterDescription(body) => {
+                    params = body
+                        .parameters()
+                        .collect()
+                        .map_err(|e: std::io::Error| Error::Protocol(e.to_string()))?;
+                }
+                backend::Message::RowDescription(body) => {
+                    columns = parse_columns(body)?;
+                }
                 backend::Message::ReadyForQuery(_) => break,
                 backend::Message::ErrorResponse(body) => {
-                    let mut fields = body.fields();
-                    let first = FallibleIterator::next(&mut fields)
-                        .map_err(|e: std::io::Error| Error::Protocol(e.to_string()))?;
-                    let msg = first
-                        .map(|f: postgres_protocol::message::backend::ErrorField| {
-                            String::from_utf8_lossy(f.value_bytes()).into_owned()
-                        })
-                        .unwrap_or_else(|| "Unknown error".to_string());
-                    error = Some(Error::Protocol(msg));
+                    error = Some(error_response(body)?);
                 }
                 _ => {}
             }
@@ -416,6 +1155,384 @@ impl Connection {
         if let Some(e) = error {
             return Err(e);
         }
+        Ok((params, columns))
+    }
+
+    /// Returns the type with OID `oid`. Types `postgres-types` does not know,
+    /// such as enums, composites, domains and those from extensions, are
+    /// looked up in the catalog the first time they are seen.
+    async fn get_type(&mut self, oid: u32) -> Result<Type> {
+        if let Some(ty) = Type::from_oid(oid) {
+            return Ok(ty);
+        }
+        if let Some(ty) = self.types.get(&oid) {
+            return Ok(ty.clone());
+        }
+
+        let rows = self.type_info_query(TYPE_INFO_QUERY, oid).await?;
+        let row = rows
+            .first()
+            .ok_or_else(|| Error::Protocol(format!("unknown type OID {}", oid)))?;
+        let name: String = row.try_get(0)?;
+        let typtype: i8 = row.try_get(1)?;
+        let elem_oid: u32 = row.try_get(2)?;
+        let range_subtype: Option<u32> = row.try_get(3)?;
+        let base_type: u32 = row.try_get(4)?;
+        let schema: String = row.try_get(5)?;
+        let relid: u32 = row.try_get(6)?;
+
+        let kind = if typtype == b'e' as i8 {
+            let rows = self.type_info_query(TYPE_INFO_ENUM_QUERY, oid).await?;
+            Kind::Enum(
+                rows.iter()
+                    .map(|row| row.try_get(0))
+                    .collect::<Result<_>>()?,
+            )
+        } else if typtype == b'p' as i8 {
+            Kind::Pseudo
+        } else if base_type != 0 {
+            Kind::Domain(Box::pin(self.get_type(base_type)).await?)
+        } else if elem_oid != 0 {
+            Kind::Array(Box::pin(self.get_type(elem_oid)).await?)
+        } else if relid != 0 {
+            let rows = self
+                .type_info_query(TYPE_INFO_COMPOSITE_QUERY, relid)
+                .await?;
+            let mut fields = Vec::with_capacity(rows.len());
+            for row in &rows {
+                let field_name: String = row.try_get(0)?;
+                let field_oid: u32 = row.try_get(1)?;
+                let field_type = Box::pin(self.get_type(field_oid)).await?;
+                fields.push(Field::new(field_name, field_type));
+            }
+            Kind::Composite(fields)
+        } else if let Some(subtype) = range_subtype {
+            Kind::Range(Box::pin(self.get_type(subtype)).await?)
+        } else {
+            Kind::Simple
+        };
+
+        let ty = Type::new(name, oid, kind, schema);
+        self.types.insert(oid, ty.clone());
+        Ok(ty)
+    }
+
+    /// Runs one of the catalog queries used by [`Connection::get_type`] as
+    /// the unnamed statement, so it does not touch the statement cache.
+    async fn type_info_query(&mut self, query: &str, oid: u32) -> Result<Vec<Row>> {
+        // The catalog columns are all of built-in types.
+        let (_, columns) = self.describe_statement("", query, &[Type::OID]).await?;
+        let stmt = Statement::unnamed(query, vec![Type::OID], columns);
+        self.bind_execute(&stmt, &[&oid], 0).await?;
+        let (rows, _) = self.read_response(&stmt, true).await??;
+        Ok(rows)
+    }
+
+    /// Writes `Bind`/`Execute`/`Sync` for `stmt` and flushes them. See
+    /// [`encode_bind_execute`] for the meaning of `max_rows`.
+    async fn bind_execute(
+        &mut self,
+        stmt: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+        max_rows: i32,
+    ) -> Result<()> {
+        self.write_buf.clear();
+        encode_bind_execute(&mut self.write_buf, stmt, params, max_rows)?;
+        self.flush_write_buf().await
+    }
+
+    /// Prepares `query` if needed and appends its `Bind`/`Execute`/`Sync` to
+    /// `buf` without sending anything, returning the columns of its rows.
+    pub(crate) async fn pipeline_query<T>(
+        &mut self,
+        buf: &mut BytesMut,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Statement>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.run_deferred().await?;
+        let stmt = self.resolve(statement).await?;
+        encode_bind_execute(buf, &stmt, params, 0)?;
+        Ok(stmt)
+    }
+
+    /// Sends messages built up by [`Connection::pipeline_query`] in one write.
+    pub(crate) async fn pipeline_send(&mut self, buf: BytesMut) -> Result<()> {
+        self.write_buf = buf;
+        self.flush_write_buf().await
+    }
+
+    async fn flush_write_buf(&mut self) -> Result<()> {
+        self.check_open()?;
+        self.in_flight += count_ready_for_query(&self.write_buf);
+        self.io_pending = true;
+        let res = async {
+            self.framed.send(self.write_buf.split()).await?;
+            self.framed.flush().await
+        }
+        .await
+        .map_err(|e| Error::Other(e.to_string()));
+        self.io_pending = false;
+        if res.is_err() {
+            self.broken = true;
+        }
+        res
+    }
+
+    /// Prepares `query` as a named statement, outside the statement cache.
+    pub async fn prepare(&mut self, query: &str) -> Result<Statement> {
+        self.prepare_typed(query, &[]).await
+    }
+
+    /// Like [`Connection::prepare`], but with the types of the first
+    /// `types.len()` parameters given instead of inferred.
+    pub async fn prepare_typed(&mut self, query: &str, types: &[Type]) -> Result<Statement> {
+        self.run_deferred().await?;
+        self.prepare_statement(query, types, true).await
+    }
+
+    /// Closes `statement` on the server now instead of before the next
+    /// request. Does nothing while clones of it are still alive.
+    pub async fn close_statement(&mut self, statement: Statement) -> Result<()> {
+        drop(statement);
+        self.run_deferred().await
+    }
+
+    /// Ends the session with `Terminate` and waits for the server to close
+    /// the socket, so it does not log an unexpected EOF. Anything still owed
+    /// for abandoned requests is discarded. A connection that is already
+    /// closed is just dropped.
+    pub async fn close(mut self) -> Result<()> {
+        if self.is_closed() {
+            return Ok(());
+        }
+        self.write_buf.clear();
+        frontend::terminate(&mut self.write_buf);
+        self.flush_write_buf().await?;
+        monoio::io::AsyncWriteRent::shutdown(&mut self.framed).await?;
+        while let Some(Ok(_)) = self.framed.next().await {}
         Ok(())
     }
 }
+
+/// Awaits `future`, a request on the connection whose token is given with
+/// `timeout`. Once the timeout passes the request is cancelled, and the rest
+/// of its response is awaited for as long again. Returns `None` if it still
+/// has not finished, leaving the connection in an unknown state.
+async fn with_statement_timeout<F, T>(
+    timeout: Option<(Duration, CancelToken)>,
+    future: F,
+) -> Option<Result<T>>
+where
+    F: Future<Output = Result<T>>,
+{
+    match timeout {
+        Some((timeout, token)) => {
+            monoio::time::timeout(timeout * 2, token.timeout(timeout, future))
+                .await
+                .ok()
+        }
+        None => Some(future.await),
+    }
+}
+
+/// Appends `Bind`/`Execute` for `stmt` to `buf`, followed by `Sync`.
+/// Parameters are encoded in binary against the types the server described.
+/// If they cannot be encoded, `buf` is left as it was.
+///
+/// A non-zero `max_rows` limits the `Execute` to that many rows and writes
+/// `Flush` instead of `Sync`, so the portal stays open and the server answers
+/// with `PortalSuspended` until it is asked for more.
+fn encode_bind_execute(
+    buf: &mut BytesMut,
+    stmt: &Statement,
+    params: &[&(dyn ToSql + Sync)],
+    max_rows: i32,
+) -> Result<()> {
+    if params.len() != stmt.params().len() {
+        return Err(Error::Parameter(format!(
+            "expected {} parameters but got {}",
+            stmt.params().len(),
+            params.len()
+        )));
+    }
+
+    let start = buf.len();
+    if stmt.name().is_empty() {
+        // The unnamed statement may have been replaced since it was
+        // described, so parse it again with the types the server chose.
+        frontend::parse(
+            "",
+            stmt.query(),
+            stmt.params().iter().map(|ty| match *ty {
+                Type::UNKNOWN => 0,
+                _ => ty.oid(),
+            }),
+            buf,
+        )
+        .map_err(|e| Error::Protocol(e.to_string()))?;
+    }
+    let res = frontend::bind(
+        "",
+        stmt.name(),
+        std::iter::once(1),
+        params.iter().zip(stmt.params().iter()),
+        |(param, ty), buf| match param.to_sql_checked(ty, buf)? {
+            postgres_types::IsNull::No => Ok(postgres_protocol::IsNull::No),
+            postgres_types::IsNull::Yes => Ok(postgres_protocol::IsNull::Yes),
+        },
+        std::iter::once(1),
+        buf,
+    );
+    match res {
+        Ok(()) => {}
+        Err(frontend::BindError::Conversion(e)) => {
+            buf.truncate(start);
+            return Err(Error::Parameter(e.to_string()));
+        }
+        Err(frontend::BindError::Serialization(e)) => {
+            buf.truncate(start);
+            return Err(Error::Protocol(e.to_string()));
+        }
+    }
+
+    frontend::execute("", max_rows, buf).map_err(|e| Error::Protocol(e.to_string()))?;
+    if max_rows == 0 {
+        frontend::sync(buf);
+    } else {
+        frontend::flush(buf);
+    }
+    Ok(())
+}
+
+/// Returns how many `ReadyForQuery` messages the server will answer the
+/// frontend messages in `buf` with: one for each `Sync` and `Query`.
+fn count_ready_for_query(mut buf: &[u8]) -> usize {
+    let mut count = 0;
+    while buf.len() >= 5 {
+        if matches!(buf[0], b'S' | b'Q') {
+            count += 1;
+        }
+        let len = i32::from_be_bytes([buf[1], buf[2], buf[3], buf[4]]) as usize;
+        buf = &buf[(len + 1).min(buf.len())..];
+    }
+    count
+}
+
+/// Whether `error` means a prepared statement no longer matches the server:
+/// `0A000` for "cached plan must not change result type" after a schema
+/// change, or `26000` if the statement has been deallocated. Other `0A000`
+/// errors are real unsupported features, which must not be retried.
+fn is_stale_statement(error: &Error) -> bool {
+    let Some(db) = error.as_db_error() else {
+        return false;
+    };
+    match db.code {
+        SqlState::InvalidSqlStatementName => true,
+        SqlState::FeatureNotSupported => match &db.routine {
+            Some(routine) => routine == "RevalidateCachedQuery",
+            None => db.message == "cached plan must not change result type",
+        },
+        _ => false,
+    }
+}
+
+fn parse_columns(body: backend::RowDescriptionBody) -> Result<Vec<Column>> {
+    let mut cols = Vec::new();
+    let mut fields = body.fields();
+    while let Some(field) = FallibleIterator::next(&mut fields)
+        .map_err(|e: std::io::Error| Error::Protocol(e.to_string()))?
+    {
+        cols.push(Column {
+            name: field.name().to_string(),
+            table_oid: field.table_oid(),
+            column_id: field.column_id(),
+            type_oid: field.type_oid(),
+            type_: Type::from_oid(field.type_oid()).unwrap_or(Type::UNKNOWN),
+            type_len: field.type_size(),
+            type_mod: field.type_modifier(),
+            // Statements are described before binding, so the server reports
+            // text format here; results are always requested in binary.
+            format: 1,
+        });
+    }
+    Ok(cols)
+}
+
+/// Splits a raw `DataRow` message into its column values.
+pub(crate) fn data_row(raw: &Bytes, columns: &Arc<Vec<Column>>) -> Result<Row> {
+    let invalid = || Error::Protocol("invalid DataRow message".into());
+    let count = raw
+        .get(5..7)
+        .map(|b| i16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(invalid)?;
+    if usize::try_from(count).ok() != Some(columns.len()) {
+        return Err(Error::Protocol(format!(
+            "DataRow has {} values for {} columns",
+            count,
+            columns.len()
+        )));
+    }
+
+    let mut data = Vec::with_capacity(columns.len());
+    let mut cursor = 7; // tag(1) + len(4) + col_count(2)
+    for _ in 0..columns.len() {
+        let len = raw.get(cursor..cursor + 4).ok_or_else(invalid)?;
+        let len = i32::from_be_bytes([len[0], len[1], len[2], len[3]]);
+        cursor += 4;
+        if len == -1 {
+            data.push(None);
+        } else {
+            let end = usize::try_from(len)
+                .ok()
+                .map(|len| cursor + len)
+                .filter(|&end| end <= raw.len())
+                .ok_or_else(invalid)?;
+            data.push(Some(raw.slice(cursor..end)));
+            cursor = end;
+        }
+    }
+
+    Ok(Row {
+        columns: columns.clone(),
+        data,
+    })
+}
+
+pub(crate) fn command_tag(body: &backend::CommandCompleteBody) -> Result<CommandTag> {
+    body.tag()
+        .map(CommandTag::parse)
+        .map_err(|e| Error::Protocol(e.to_string()))
+}
+
+fn parameter_status(body: backend::ParameterStatusBody) -> Result<(String, String)> {
+    let name = body
+        .name()
+        .map_err(|e| Error::Protocol(e.to_string()))?
+        .to_string();
+    let value = body
+        .value()
+        .map_err(|e| Error::Protocol(e.to_string()))?
+        .to_string();
+    Ok((name, value))
+}
+
+fn notification(body: backend::NotificationResponseBody) -> Result<Notification> {
+    Ok(Notification {
+        process_id: body.process_id(),
+        channel: body
+            .channel()
+            .map_err(|e| Error::Protocol(e.to_string()))?
+            .to_string(),
+        payload: body
+            .message()
+            .map_err(|e| Error::Protocol(e.to_string()))?
+            .to_string(),
+    })
+}
+
+pub(crate) fn error_response(body: backend::ErrorResponseBody) -> Result<Error> {
+    DbError::parse(&mut body.fields()).map(Error::from)
+}
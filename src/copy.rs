@@ -0,0 +1,531 @@
+use crate::client::CommandTag;
+use crate::connection::{self, Connection};
+use crate::error::{Error, Result};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use monoio::io::stream::Stream;
+use postgres_protocol::message::{backend, frontend};
+use postgres_types::{FromSql, IsNull, ToSql, Type};
+use std::sync::Arc;
+
+/// Buffered data is sent once it grows past this many bytes.
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+const BINARY_SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+/// A `COPY ... FROM STDIN` in progress, created by
+/// [`Client::copy_in`](crate::Client::copy_in).
+///
+/// Data is buffered and sent in large `CopyData` messages. It must match the
+/// format named in the `COPY` statement: [`CopyInSink::send`] takes raw
+/// bytes, while [`CopyInSink::send_text_row`] and [`CopyInSink::send_csv_row`]
+/// encode one row in the default `text` or `csv` format. For `FORMAT binary`,
+/// use [`CopyInSink::binary`].
+///
+/// Call [`CopyInSink::finish`] to commit the data. A sink that is dropped
+/// without finishing sends `CopyFail`, so nothing it sent is loaded.
+pub struct CopyInSink<'a> {
+    connection: &'a mut Connection,
+    buf: BytesMut,
+    done: bool,
+}
+
+impl<'a> CopyInSink<'a> {
+    pub(crate) async fn start(connection: &'a mut Connection, query: &str) -> Result<Self> {
+        connection.copy_in_start(query).await?;
+        Ok(Self {
+            connection,
+            buf: BytesMut::with_capacity(COPY_BUFFER_SIZE),
+            done: false,
+        })
+    }
+
+    /// Sends raw bytes in whatever format the `COPY` statement expects. Rows
+    /// may be split across calls.
+    pub async fn send(&mut self, data: &[u8]) -> Result<()> {
+        self.buf.extend_from_slice(data);
+        self.flush_if_full().await
+    }
+
+    /// Sends one row in the `text` format: tab-separated, with `None` written
+    /// as `\N` and backslashes, newlines, carriage returns and tabs escaped.
+    pub async fn send_text_row(&mut self, row: &[Option<&str>]) -> Result<()> {
+        for (i, field) in row.iter().enumerate() {
+            if i > 0 {
+                self.buf.put_u8(b'\t');
+            }
+            match field {
+                None => self.buf.extend_from_slice(b"\\N"),
+                Some(s) => {
+                    for &b in s.as_bytes() {
+                        match b {
+                            b'\\' => self.buf.extend_from_slice(b"\\\\"),
+                            b'\n' => self.buf.extend_from_slice(b"\\n"),
+                            b'\r' => self.buf.extend_from_slice(b"\\r"),
+                            b'\t' => self.buf.extend_from_slice(b"\\t"),
+                            _ => self.buf.put_u8(b),
+                        }
+                    }
+                }
+            }
+        }
+        self.buf.put_u8(b'\n');
+        self.flush_if_full().await
+    }
+
+    /// Sends one row in the `csv` format with the default `,` delimiter and
+    /// `"` quote. `None` is written as an unquoted empty field, which is how
+    /// CSV spells `NULL`, so empty strings are always quoted.
+    pub async fn send_csv_row(&mut self, row: &[Option<&str>]) -> Result<()> {
+        for (i, field) in row.iter().enumerate() {
+            if i > 0 {
+                self.buf.put_u8(b',');
+            }
+            if let Some(s) = field {
+                let quote = s.is_empty()
+                    || *s == "\\."
+                    || s.bytes().any(|b| matches!(b, b',' | b'"' | b'\n' | b'\r'));
+                if quote {
+                    self.buf.put_u8(b'"');
+                    for &b in s.as_bytes() {
+                        if b == b'"' {
+                            self.buf.put_u8(b'"');
+                        }
+                        self.buf.put_u8(b);
+                    }
+                    self.buf.put_u8(b'"');
+                } else {
+                    self.buf.extend_from_slice(s.as_bytes());
+                }
+            }
+        }
+        self.buf.put_u8(b'\n');
+        self.flush_if_full().await
+    }
+
+    /// Switches to writing rows in the `binary` format, encoding values with
+    /// [`ToSql`] as the given column types.
+    pub fn binary(mut self, types: &[Type]) -> BinaryCopyInWriter<'a> {
+        self.buf.extend_from_slice(BINARY_SIGNATURE);
+        self.buf.put_i32(0); // flags
+        self.buf.put_i32(0); // header extension length
+        BinaryCopyInWriter {
+            sink: self,
+            types: types.to_vec(),
+        }
+    }
+
+    /// Sends any buffered data and `CopyDone`, and returns the number of rows
+    /// loaded.
+    pub async fn finish(mut self) -> Result<u64> {
+        self.done = true;
+        self.flush().await?;
+        self.connection.copy_in_finish().await
+    }
+
+    /// Aborts the copy. Nothing sent so far is loaded.
+    pub async fn abort(mut self, message: &str) -> Result<()> {
+        self.done = true;
+        self.connection.copy_in_fail(message).await
+    }
+
+    async fn flush_if_full(&mut self) -> Result<()> {
+        if self.buf.len() >= COPY_BUFFER_SIZE {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if !self.buf.is_empty() {
+            let data = self.buf.split().freeze();
+            self.connection.copy_data(data).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CopyInSink<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            let prelude = self.connection.defer_drain();
+            let _ = frontend::copy_fail("COPY aborted by client", prelude);
+        }
+    }
+}
+
+/// Writes rows for a `COPY ... FROM STDIN (FORMAT binary)`, created by
+/// [`CopyInSink::binary`].
+pub struct BinaryCopyInWriter<'a> {
+    sink: CopyInSink<'a>,
+    types: Vec<Type>,
+}
+
+impl BinaryCopyInWriter<'_> {
+    /// Writes one row. There must be one value per column type.
+    pub async fn write(&mut self, values: &[&(dyn ToSql + Sync)]) -> Result<()> {
+        if values.len() != self.types.len() {
+            return Err(Error::Parameter(format!(
+                "expected {} values but got {}",
+                self.types.len(),
+                values.len()
+            )));
+        }
+
+        // Encode into a scratch buffer first, so a value that fails to
+        // convert does not leave half a row in the copy data.
+        let mut row = BytesMut::new();
+        row.put_i16(self.types.len() as i16);
+        for (value, ty) in values.iter().zip(&self.types) {
+            let len_at = row.len();
+            row.put_i32(0);
+            match value
+                .to_sql_checked(ty, &mut row)
+                .map_err(|e| Error::Parameter(e.to_string()))?
+            {
+                IsNull::No => {
+                    let len = (row.len() - len_at - 4) as i32;
+                    row[len_at..len_at + 4].copy_from_slice(&len.to_be_bytes());
+                }
+                IsNull::Yes => {
+                    row[len_at..len_at + 4].copy_from_slice(&(-1i32).to_be_bytes());
+                }
+            }
+        }
+
+        self.sink.buf.extend_from_slice(&row);
+        self.sink.flush_if_full().await
+    }
+
+    /// Writes the trailer and finishes the copy, returning the number of rows
+    /// loaded.
+    pub async fn finish(mut self) -> Result<u64> {
+        self.sink.buf.put_i16(-1);
+        self.sink.finish().await
+    }
+
+    /// Aborts the copy. Nothing written so far is loaded.
+    pub async fn abort(self, message: &str) -> Result<()> {
+        self.sink.abort(message).await
+    }
+}
+
+/// The data of a `COPY ... TO STDOUT`, created by
+/// [`Client::copy_out`](crate::Client::copy_out).
+///
+/// Each item is the payload of one `CopyData` message, in the format named
+/// in the `COPY` statement. The server sends one message per row, but that is
+/// not guaranteed, so callers should not rely on it. A server error is
+/// returned as the last item.
+///
+/// Dropping the stream early is allowed; the rest of the data is read and
+/// discarded before the connection is used again.
+pub struct CopyOutStream<'a> {
+    connection: &'a mut Connection,
+    done: bool,
+    tag: Option<CommandTag>,
+    error: Option<Error>,
+}
+
+impl<'a> CopyOutStream<'a> {
+    pub(crate) async fn start(connection: &'a mut Connection, query: &str) -> Result<Self> {
+        connection.copy_out_start(query).await?;
+        Ok(Self {
+            connection,
+            done: false,
+            tag: None,
+            error: None,
+        })
+    }
+
+    /// Returns the command tag, which holds the number of rows copied, once
+    /// all of the data has been read.
+    pub fn command_tag(&self) -> Option<&CommandTag> {
+        self.tag.as_ref()
+    }
+
+    /// Decodes the data as `FORMAT binary` rows with the given column types.
+    pub fn binary(self, types: &[Type]) -> BinaryCopyOutStream<'a> {
+        BinaryCopyOutStream {
+            stream: self,
+            types: Arc::new(types.to_vec()),
+            buf: BytesMut::new(),
+            header_read: false,
+            trailer_read: false,
+            done: false,
+        }
+    }
+
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>> {
+        loop {
+            let (msg, _raw) = self.connection.recv().await?;
+            match msg {
+                backend::Message::CopyData(body) => return Ok(Some(body.into_bytes())),
+                backend::Message::CommandComplete(body) => {
+                    self.tag = Some(connection::command_tag(&body)?);
+                }
+                backend::Message::ErrorResponse(body) => {
+                    self.error = Some(connection::error_response(body)?);
+                }
+                backend::Message::ReadyForQuery(_) => {
+                    self.done = true;
+                    return match self.error.take() {
+                        Some(e) => Err(e),
+                        None => Ok(None),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Stream for CopyOutStream<'_> {
+    type Item = Result<Bytes>;
+
+    async fn next(&mut self) -> Option<Result<Bytes>> {
+        if self.done {
+            return None;
+        }
+        match self.next_chunk().await {
+            Ok(chunk) => chunk.map(Ok),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Rows of a `COPY ... TO STDOUT (FORMAT binary)`, created by
+/// [`CopyOutStream::binary`].
+pub struct BinaryCopyOutStream<'a> {
+    stream: CopyOutStream<'a>,
+    types: Arc<Vec<Type>>,
+    buf: BytesMut,
+    header_read: bool,
+    trailer_read: bool,
+    /// Set once the stream has ended or returned an error.
+    done: bool,
+}
+
+impl BinaryCopyOutStream<'_> {
+    /// Returns the command tag once all of the rows have been read.
+    pub fn command_tag(&self) -> Option<&CommandTag> {
+        self.stream.command_tag()
+    }
+
+    async fn next_row(&mut self) -> Option<Result<BinaryCopyOutRow>> {
+        loop {
+            if !self.trailer_read {
+                if !self.header_read {
+                    match parse_binary_header(&mut self.buf) {
+                        Ok(true) => self.header_read = true,
+                        Ok(false) => {}
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                if self.header_read {
+                    match parse_binary_row(&mut self.buf, &self.types) {
+                        Ok(Some(Some(data))) => {
+                            return Some(Ok(BinaryCopyOutRow {
+                                types: self.types.clone(),
+                                data,
+                            }));
+                        }
+                        Ok(Some(None)) => self.trailer_read = true,
+                        Ok(None) => {}
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+            }
+
+            match self.stream.next().await {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(e)) => return Some(Err(e)),
+                None if self.trailer_read => return None,
+                None => {
+                    return Some(Err(Error::Protocol(
+                        "binary COPY data ended without a trailer".into(),
+                    )));
+                }
+            }
+        }
+    }
+}
+
+impl Stream for BinaryCopyOutStream<'_> {
+    type Item = Result<BinaryCopyOutRow>;
+
+    async fn next(&mut self) -> Option<Result<BinaryCopyOutRow>> {
+        if self.done {
+            return None;
+        }
+        let row = self.next_row().await;
+        self.done = !matches!(row, Some(Ok(_)));
+        row
+    }
+}
+
+/// One row of a binary `COPY`.
+pub struct BinaryCopyOutRow {
+    types: Arc<Vec<Type>>,
+    data: Vec<Option<Bytes>>,
+}
+
+impl BinaryCopyOutRow {
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Decodes the value at `index` as the column type given to
+    /// [`CopyOutStream::binary`].
+    pub fn get<'a, T: FromSql<'a>>(&'a self, index: usize) -> Result<T> {
+        let ty = self
+            .types
+            .get(index)
+            .ok_or_else(|| Error::Parse(format!("Column index {} out of bounds", index)))?;
+        if !T::accepts(ty) {
+            return Err(Error::Parse(format!(
+                "cannot convert column {} of type {} to {}",
+                index,
+                ty,
+                std::any::type_name::<T>()
+            )));
+        }
+        match &self.data[index] {
+            Some(b) => T::from_sql(ty, b).map_err(|e| Error::Parse(e.to_string())),
+            None => T::from_sql_null(ty).map_err(|e| Error::Parse(e.to_string())),
+        }
+    }
+
+    pub fn get_raw(&self, index: usize) -> Option<&Bytes> {
+        self.data.get(index).and_then(|opt| opt.as_ref())
+    }
+}
+
+/// Consumes the binary `COPY` header from `buf` if all of it has arrived.
+fn parse_binary_header(buf: &mut BytesMut) -> Result<bool> {
+    let fixed = BINARY_SIGNATURE.len() + 8;
+    if buf.len() < fixed {
+        return Ok(false);
+    }
+    if &buf[..BINARY_SIGNATURE.len()] != BINARY_SIGNATURE {
+        return Err(Error::Protocol("invalid binary COPY signature".into()));
+    }
+    let ext_len = i32::from_be_bytes(buf[fixed - 4..fixed].try_into().unwrap());
+    if ext_len < 0 {
+        return Err(Error::Protocol("invalid binary COPY header".into()));
+    }
+    let len = fixed + ext_len as usize;
+    if buf.len() < len {
+        return Ok(false);
+    }
+    buf.advance(len);
+    Ok(true)
+}
+
+/// Consumes one tuple from `buf` if all of it has arrived. Returns
+/// `Some(None)` for the trailer that ends the data.
+fn parse_binary_row(
+    buf: &mut BytesMut,
+    types: &[Type],
+) -> Result<Option<Option<Vec<Option<Bytes>>>>> {
+    if buf.len() < 2 {
+        return Ok(None);
+    }
+    let count = i16::from_be_bytes([buf[0], buf[1]]);
+    if count == -1 {
+        buf.advance(2);
+        return Ok(Some(None));
+    }
+    if count as usize != types.len() {
+        return Err(Error::Protocol(format!(
+            "expected {} columns in binary COPY row but got {}",
+            types.len(),
+            count
+        )));
+    }
+
+    // Find the end of the tuple before consuming anything.
+    let mut fields = Vec::with_capacity(types.len());
+    let mut cursor = 2;
+    for _ in 0..count {
+        if buf.len() < cursor + 4 {
+            return Ok(None);
+        }
+        let len = i32::from_be_bytes(buf[cursor..cursor + 4].try_into().unwrap());
+        cursor += 4;
+        if len < 0 {
+            fields.push(None);
+        } else {
+            let len = len as usize;
+            if buf.len() < cursor + len {
+                return Ok(None);
+            }
+            fields.push(Some(cursor..cursor + len));
+            cursor += len;
+        }
+    }
+
+    let tuple = buf.split_to(cursor).freeze();
+    Ok(Some(Some(
+        fields
+            .into_iter()
+            .map(|range| range.map(|r| tuple.slice(r)))
+            .collect(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_binary_copy_split() {
+        let mut data = BytesMut::new();
+        data.extend_from_slice(BINARY_SIGNATURE);
+        data.put_i32(0);
+        data.put_i32(2);
+        data.put_slice(b"xx"); // header extension, skipped
+        data.put_i16(2);
+        data.put_i32(4);
+        data.put_i32(42);
+        data.put_i32(-1);
+        data.put_i16(-1);
+
+        let types = [Type::INT4, Type::TEXT];
+        let mut buf = BytesMut::new();
+        let mut header_read = false;
+        let mut rows = Vec::new();
+        let mut trailer = false;
+        // Feed one byte at a time to check every split point.
+        for b in data.iter() {
+            buf.put_u8(*b);
+            if !header_read {
+                header_read = parse_binary_header(&mut buf).unwrap();
+                continue;
+            }
+            match parse_binary_row(&mut buf, &types).unwrap() {
+                Some(Some(row)) => rows.push(row),
+                Some(None) => trailer = true,
+                None => {}
+            }
+        }
+
+        assert!(trailer);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0][0].as_deref(), Some(&42i32.to_be_bytes()[..]));
+        assert_eq!(rows[0][1], None);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_parse_binary_copy_bad_signature() {
+        let mut buf = BytesMut::from(&b"PGCOPY\n\xff\r\nX\0\0\0\0\0\0\0\0"[..]);
+        assert!(parse_binary_header(&mut buf).is_err());
+    }
+}
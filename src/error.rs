@@ -1,3 +1,7 @@
+use crate::sqlstate::SqlState;
+use fallible_iterator::FallibleIterator;
+use postgres_protocol::message::backend::ErrorFields;
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -8,17 +12,292 @@ pub enum Error {
     #[error("Postgres protocol error: {0}")]
     Protocol(String),
 
+    #[error("Database error: {0}")]
+    Db(Box<DbError>),
+
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    #[error("TLS error: {0}")]
+    Tls(String),
+
     #[error("Authentication failed: {0}")]
     Authentication(String),
 
     #[error("Parse error: {0}")]
     Parse(String),
 
+    #[error("Parameter error: {0}")]
+    Parameter(String),
+
+    #[error("Timed out {0}")]
+    Timeout(String),
+
     #[error("Connection closed")]
     Closed,
 
+    /// A request was made on a connection that an earlier failure left
+    /// unusable. See [`Client::is_closed`](crate::Client::is_closed).
+    #[error("Connection is broken by an earlier error")]
+    Broken,
+
     #[error("Other error: {0}")]
     Other(String),
 }
 
+impl Error {
+    /// Returns the server error if this is an [`Error::Db`].
+    pub fn as_db_error(&self) -> Option<&DbError> {
+        match self {
+            Error::Db(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the SQLSTATE code if this is an [`Error::Db`].
+    pub fn code(&self) -> Option<&SqlState> {
+        self.as_db_error().map(|e| &e.code)
+    }
+}
+
+impl From<DbError> for Error {
+    fn from(e: DbError) -> Self {
+        Error::Db(Box::new(e))
+    }
+}
+
+/// Where in the query text an error occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorPosition {
+    /// A 1-based character offset into the query sent by the client.
+    Original(u32),
+    /// A 1-based character offset into an internally generated query, such as
+    /// one issued by a PL/pgSQL function.
+    Internal { position: u32, query: String },
+}
+
+/// An error reported by the server in an `ErrorResponse` message.
+///
+/// Field meanings follow the "Error and Notice Message Fields" section of the
+/// PostgreSQL protocol documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbError {
+    /// `ERROR`, `FATAL` or `PANIC`, possibly localized.
+    pub severity: String,
+    pub code: SqlState,
+    pub message: String,
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+    pub position: Option<ErrorPosition>,
+    /// The call stack context in which the error occurred.
+    pub where_: Option<String>,
+    pub schema: Option<String>,
+    pub table: Option<String>,
+    pub column: Option<String>,
+    pub datatype: Option<String>,
+    pub constraint: Option<String>,
+    /// Source file, line and routine in the server that reported the error.
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub routine: Option<String>,
+}
+
+impl DbError {
+    pub(crate) fn parse(fields: &mut ErrorFields<'_>) -> Result<DbError> {
+        let mut severity = None;
+        let mut parsed_severity = None;
+        let mut code = None;
+        let mut message = None;
+        let mut detail = None;
+        let mut hint = None;
+        let mut normal_position = None;
+        let mut internal_position = None;
+        let mut internal_query = None;
+        let mut where_ = None;
+        let mut schema = None;
+        let mut table = None;
+        let mut column = None;
+        let mut datatype = None;
+        let mut constraint = None;
+        let mut file = None;
+        let mut line = None;
+        let mut routine = None;
+
+        while let Some(field) = fields
+            .next()
+            .map_err(|e: std::io::Error| Error::Protocol(e.to_string()))?
+        {
+            let value = String::from_utf8_lossy(field.value_bytes()).into_owned();
+            match field.type_() {
+                b'S' => severity = Some(value),
+                b'V' => parsed_severity = Some(value),
+                b'C' => code = Some(SqlState::from_code(&value)),
+                b'M' => message = Some(value),
+                b'D' => detail = Some(value),
+                b'H' => hint = Some(value),
+                b'P' => normal_position = Some(parse_u32(&value, "P")?),
+                b'p' => internal_position = Some(parse_u32(&value, "p")?),
+                b'q' => internal_query = Some(value),
+                b'W' => where_ = Some(value),
+                b's' => schema = Some(value),
+                b't' => table = Some(value),
+                b'c' => column = Some(value),
+                b'd' => datatype = Some(value),
+                b'n' => constraint = Some(value),
+                b'F' => file = Some(value),
+                b'L' => line = Some(parse_u32(&value, "L")?),
+                b'R' => routine = Some(value),
+                // Unknown field types must be ignored per the protocol docs.
+                _ => {}
+            }
+        }
+
+        let position = match (normal_position, internal_position) {
+            (Some(position), _) => Some(ErrorPosition::Original(position)),
+            (None, Some(position)) => Some(ErrorPosition::Internal {
+                position,
+                query: internal_query.ok_or_else(|| {
+                    Error::Protocol("`q` field missing but `p` field present".into())
+                })?,
+            }),
+            (None, None) => None,
+        };
+
+        Ok(DbError {
+            severity: parsed_severity
+                .or(severity)
+                .ok_or_else(|| Error::Protocol("`S` field missing".into()))?,
+            code: code.ok_or_else(|| Error::Protocol("`C` field missing".into()))?,
+            message: message.ok_or_else(|| Error::Protocol("`M` field missing".into()))?,
+            detail,
+            hint,
+            position,
+            where_,
+            schema,
+            table,
+            column,
+            datatype,
+            constraint,
+            file,
+            line,
+            routine,
+        })
+    }
+}
+
+fn parse_u32(value: &str, field: &str) -> Result<u32> {
+    value
+        .parse()
+        .map_err(|_| Error::Protocol(format!("invalid `{}` field: {}", field, value)))
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        if let Some(detail) = &self.detail {
+            write!(f, "\nDETAIL: {}", detail)?;
+        }
+        if let Some(hint) = &self.hint {
+            write!(f, "\nHINT: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DbError {}
+
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::{BufMut, BytesMut};
+    use postgres_protocol::message::backend::Message;
+
+    fn error_message(fields: &[(u8, &str)]) -> BytesMut {
+        let mut body = BytesMut::new();
+        for (ty, value) in fields {
+            body.put_u8(*ty);
+            body.put_slice(value.as_bytes());
+            body.put_u8(0);
+        }
+        body.put_u8(0);
+
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'E');
+        buf.put_i32(body.len() as i32 + 4);
+        buf.unsplit(body);
+        buf
+    }
+
+    fn parse(fields: &[(u8, &str)]) -> Result<DbError> {
+        match Message::parse(&mut error_message(fields)).unwrap() {
+            Some(Message::ErrorResponse(body)) => DbError::parse(&mut body.fields()),
+            _ => panic!("Expected ErrorResponse"),
+        }
+    }
+
+    #[test]
+    fn test_parse_db_error() {
+        let err = parse(&[
+            (b'S', "ERREUR"),
+            (b'V', "ERROR"),
+            (b'C', "23505"),
+            (
+                b'M',
+                "duplicate key value violates unique constraint \"users_pkey\"",
+            ),
+            (b'D', "Key (id)=(1) already exists."),
+            (b's', "public"),
+            (b't', "users"),
+            (b'n', "users_pkey"),
+            (b'F', "nbtinsert.c"),
+            (b'L', "666"),
+            (b'R', "_bt_check_unique"),
+            (b'Z', "ignored"),
+        ])
+        .unwrap();
+
+        assert_eq!(err.severity, "ERROR");
+        assert_eq!(err.code, SqlState::UniqueViolation);
+        assert_eq!(err.detail.as_deref(), Some("Key (id)=(1) already exists."));
+        assert_eq!(err.table.as_deref(), Some("users"));
+        assert_eq!(err.constraint.as_deref(), Some("users_pkey"));
+        assert_eq!(err.line, Some(666));
+        assert_eq!(err.position, None);
+    }
+
+    #[test]
+    fn test_parse_db_error_internal_position() {
+        let err = parse(&[
+            (b'S', "ERROR"),
+            (b'C', "42601"),
+            (b'M', "syntax error"),
+            (b'p', "8"),
+            (b'q', "SELECT ** FROM t"),
+        ])
+        .unwrap();
+
+        assert_eq!(err.code, SqlState::SyntaxError);
+        assert_eq!(
+            err.position,
+            Some(ErrorPosition::Internal {
+                position: 8,
+                query: "SELECT ** FROM t".into()
+            })
+        );
+
+        assert!(parse(&[(b'S', "ERROR"), (b'M', "no code")]).is_err());
+    }
+
+    #[test]
+    fn test_sqlstate_roundtrip() {
+        assert_eq!(SqlState::from_code("40001"), SqlState::SerializationFailure);
+        assert_eq!(SqlState::SerializationFailure.code(), "40001");
+        assert_eq!(
+            SqlState::from_code("XX999"),
+            SqlState::Other("XX999".into())
+        );
+        assert_eq!(SqlState::from_code("XX999").code(), "XX999");
+    }
+}
@@ -1,10 +1,40 @@
 pub mod auth;
+pub mod cancel;
 pub mod client;
 pub mod codec;
+pub mod config;
 pub mod connection;
+pub mod copy;
 pub mod error;
+pub mod notification;
+pub mod pipeline;
 pub mod pool;
+pub mod row;
+pub mod row_stream;
+pub mod simple_query;
+pub mod sqlstate;
+pub mod statement;
+mod stream;
+pub mod tls;
+pub mod transaction;
 
-pub use client::Client;
-pub use error::{Error, Result};
-pub use pool::Pool;
+pub use cancel::CancelToken;
+pub use client::{Client, CommandTag, TransactionStatus};
+pub use config::{Config, LoadBalanceHosts, TargetSessionAttrs};
+pub use copy::{
+    BinaryCopyInWriter, BinaryCopyOutRow, BinaryCopyOutStream, CopyInSink, CopyOutStream,
+};
+pub use error::{DbError, Error, ErrorPosition, Result};
+#[cfg(feature = "derive")]
+pub use monoio_pg_derive::FromRow;
+pub use notification::{Notification, Notifications};
+pub use pipeline::Pipeline;
+pub use pool::{Pool, PoolBuilder, PooledClient};
+pub use postgres_types as types;
+pub use row::{FromRow, RowIndex};
+pub use row_stream::RowStream;
+pub use simple_query::{SimpleQueryResult, SimpleQueryRow};
+pub use sqlstate::SqlState;
+pub use statement::{Statement, ToStatement};
+pub use tls::{SslMode, TlsConfig};
+pub use transaction::{IsolationLevel, Transaction, TransactionBuilder};
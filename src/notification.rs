@@ -0,0 +1,71 @@
+use crate::connection::Connection;
+use crate::error::Result;
+use monoio::io::stream::Stream;
+use std::time::Duration;
+
+/// A message sent with `NOTIFY` on a channel the client is listening on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// The process ID of the server backend that sent the notification.
+    pub process_id: i32,
+    pub channel: String,
+    /// Empty if `NOTIFY` was given no payload.
+    pub payload: String,
+}
+
+/// Notifications received by a client, created by
+/// [`Client::notifications`](crate::Client::notifications).
+///
+/// Notifications that arrived while the client was busy with other queries
+/// are yielded first. After that, [`Stream::next`] waits for the server to
+/// send one. The stream ends after an error, which means the connection has
+/// failed.
+///
+/// Dropping a pending [`Stream::next`], for example with
+/// `monoio::time::timeout`, may lose data already read from the socket, so
+/// the client is closed. Use [`Notifications::next_timeout`] to wait with a
+/// deadline instead.
+pub struct Notifications<'a> {
+    connection: &'a mut Connection,
+    done: bool,
+}
+
+impl<'a> Notifications<'a> {
+    pub(crate) fn new(connection: &'a mut Connection) -> Self {
+        Self {
+            connection,
+            done: false,
+        }
+    }
+
+    /// Returns a notification that has already been received, without
+    /// waiting for one.
+    pub fn try_next(&mut self) -> Option<Notification> {
+        self.connection.take_notification()
+    }
+
+    /// Waits up to `timeout` for a notification, returning `None` if none
+    /// arrives. The client stays usable after a timeout. The runtime must
+    /// have the timer enabled.
+    ///
+    /// Over TLS the server is polled every 100ms with an empty query, since
+    /// the socket alone cannot tell whether a notification has arrived.
+    pub async fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Notification>> {
+        let res = self.connection.next_notification_timeout(timeout).await;
+        self.done = res.is_err();
+        res
+    }
+}
+
+impl Stream for Notifications<'_> {
+    type Item = Result<Notification>;
+
+    async fn next(&mut self) -> Option<Result<Notification>> {
+        if self.done {
+            return None;
+        }
+        let res = self.connection.next_notification().await;
+        self.done = res.is_err();
+        Some(res)
+    }
+}
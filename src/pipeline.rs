@@ -0,0 +1,75 @@
+use crate::client::{CommandTag, Row};
+use crate::connection::Connection;
+use crate::error::Result;
+use crate::statement::{Statement, ToStatement};
+use bytes::BytesMut;
+use postgres_types::ToSql;
+
+/// A batch of queries sent to the server in a single write.
+///
+/// Created by [`Client::pipeline`](crate::Client::pipeline). Each query added
+/// with [`Pipeline::query`] is bound and buffered; nothing is executed until
+/// [`Pipeline::run`] writes them all at once and reads the responses back in
+/// order, saving a round trip per query.
+///
+/// Every query ends with its own `Sync`, so an error in one does not stop the
+/// ones after it. Inside a transaction block the server still rejects every
+/// statement after a failed one until the transaction is rolled back.
+///
+/// A statement that has not been seen on this connection is prepared when it
+/// is added, which costs one round trip the first time.
+pub struct Pipeline<'a> {
+    connection: &'a mut Connection,
+    buf: BytesMut,
+    statements: Vec<Statement>,
+}
+
+impl<'a> Pipeline<'a> {
+    pub(crate) fn new(connection: &'a mut Connection) -> Self {
+        Self {
+            connection,
+            buf: BytesMut::new(),
+            statements: Vec::new(),
+        }
+    }
+
+    /// Adds a query to the batch. Its parameters are encoded straight away,
+    /// so they need not outlive this call. An error here, such as a syntax
+    /// error or a parameter of the wrong type, leaves the batch unchanged.
+    pub async fn query<T>(&mut self, statement: &T, params: &[&(dyn ToSql + Sync)]) -> Result<()>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let stmt = self
+            .connection
+            .pipeline_query(&mut self.buf, statement, params)
+            .await?;
+        self.statements.push(stmt);
+        Ok(())
+    }
+
+    /// Returns the number of queries in the batch.
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    /// Sends the batch and returns the result of each query, in the order
+    /// they were added. The outer error is an I/O or protocol failure; the
+    /// inner ones are per query.
+    pub async fn run(self) -> Result<Vec<Result<(Vec<Row>, CommandTag)>>> {
+        if self.statements.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.connection.pipeline_send(self.buf).await?;
+
+        let mut results = Vec::with_capacity(self.statements.len());
+        for stmt in &self.statements {
+            results.push(self.connection.read_response(stmt, true).await?);
+        }
+        Ok(results)
+    }
+}
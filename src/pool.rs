@@ -1,43 +1,553 @@
 use crate::client::Client;
-use crate::error::Result;
+use crate::config::Config;
+use crate::error::{Error, Result};
 use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
 
-thread_local! {
-    static POOL: RefCell<VecDeque<Client>> = RefCell::new(VecDeque::new());
-}
+/// How often the background task started by idle and lifetime limits, or a
+/// minimum size, checks the pool.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(1);
 
+/// A pool of connections to one server.
+///
+/// Like everything on `monoio`, a pool belongs to the thread that created it.
+/// Cloning a `Pool` is cheap and the clones share connections; create one
+/// pool per thread to use all cores.
+///
+/// Clients are checked out with [`Pool::get`] and returned when the
+/// [`PooledClient`] guard is dropped. Clients whose connection has failed are
+/// discarded instead of being returned, and a transaction block left open on
+/// a returned client is rolled back before it is used again. Call
+/// [`Pool::close`] at shutdown to end every session cleanly.
+///
+/// `idle_timeout`, `max_lifetime` and `min_size` are maintained by a task that
+/// runs on the monoio timer, so the runtime must be built with the timer
+/// enabled when any of them are set. The same is true of `acquire_timeout`.
+#[derive(Clone)]
 pub struct Pool {
-    addr: String,
-    user: String,
-    password: Option<String>,
-    database: Option<String>,
+    inner: Rc<PoolInner>,
+}
+
+struct PoolInner {
+    config: Config,
+    options: PoolOptions,
+    state: RefCell<PoolState>,
+}
+
+#[derive(Clone)]
+struct PoolOptions {
+    max_size: usize,
+    min_size: usize,
+    acquire_timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    max_lifetime: Option<Duration>,
+    test_query: Option<String>,
+}
+
+struct PoolState {
+    idle: VecDeque<IdleClient>,
+    /// Open connections plus connections being opened, whether idle or not.
+    size: usize,
+    waiters: VecDeque<Rc<RefCell<Waiter>>>,
+    maintenance_started: bool,
+    /// Set by [`Pool::close`]; no more clients are handed out.
+    closed: bool,
+}
+
+struct IdleClient {
+    client: Client,
+    created: Instant,
+    idle_since: Instant,
+}
+
+/// What a waiter is woken with.
+enum Handoff {
+    /// A client someone returned.
+    Client(Box<Client>, Instant),
+    /// A slot freed by a discarded connection, which the waiter may use to
+    /// open a new one.
+    Permit,
+    /// The pool was closed while waiting.
+    Closed,
+}
+
+#[derive(Default)]
+struct Waiter {
+    handoff: Option<Handoff>,
+    waker: Option<Waker>,
 }
 
 impl Pool {
     pub fn new(addr: &str, user: &str, password: Option<&str>, database: Option<&str>) -> Self {
+        Self::with_config(Config::from_addr(addr, user, password, database))
+    }
+
+    /// Creates a pool whose connections are opened with `config`, using the
+    /// default [`PoolBuilder`] settings.
+    pub fn with_config(config: Config) -> Self {
+        Self::builder(config).build()
+    }
+
+    pub fn builder(config: Config) -> PoolBuilder {
+        PoolBuilder {
+            config,
+            options: PoolOptions {
+                max_size: 10,
+                min_size: 0,
+                acquire_timeout: None,
+                idle_timeout: None,
+                max_lifetime: None,
+                test_query: None,
+            },
+        }
+    }
+
+    /// Checks out a client, opening a new connection if none are idle and the
+    /// pool is below its maximum size. Otherwise waits, in FIFO order with
+    /// other callers, for one to be returned.
+    pub async fn get(&self) -> Result<PooledClient> {
+        self.start_maintenance();
+        match self.inner.options.acquire_timeout {
+            Some(timeout) => monoio::time::timeout(timeout, self.acquire())
+                .await
+                .map_err(|_| Error::Timeout("acquiring a connection from the pool".into()))?,
+            None => self.acquire().await,
+        }
+    }
+
+    /// Returns the number of open connections, including checked-out ones.
+    pub fn size(&self) -> usize {
+        self.inner.state.borrow().size
+    }
+
+    /// Returns the number of idle connections.
+    pub fn idle(&self) -> usize {
+        self.inner.state.borrow().idle.len()
+    }
+
+    /// Closes the pool, for use at shutdown. Callers waiting in
+    /// [`Pool::get`], and any later ones, fail with [`Error::Closed`]. Every
+    /// connection is closed with [`Client::close`], so the server sees a
+    /// clean exit: idle ones before this returns and checked-out ones in the
+    /// background once they are returned. This does not wait for those, so
+    /// it is fine to call while holding a [`PooledClient`].
+    pub async fn close(&self) {
+        let (waiters, idle) = {
+            let mut state = self.inner.state.borrow_mut();
+            state.closed = true;
+            (
+                std::mem::take(&mut state.waiters),
+                std::mem::take(&mut state.idle),
+            )
+        };
+        for waiter in waiters {
+            self.inner.hand_off(waiter, Handoff::Closed);
+        }
+
+        for idle in idle {
+            // Holding a slot in `size` until the client is closed.
+            let _slot = Slot::new(&self.inner);
+            close_client(idle.client).await;
+        }
+    }
+
+    async fn acquire(&self) -> Result<PooledClient> {
+        loop {
+            // `Some(None)` is a free slot to open a new connection in, `None`
+            // means the pool is full.
+            let next = {
+                let mut state = self.inner.state.borrow_mut();
+                if state.closed {
+                    return Err(Error::Closed);
+                }
+                if let Some(idle) = state.idle.pop_front() {
+                    Some(Some(idle))
+                } else if state.size < self.inner.options.max_size {
+                    state.size += 1;
+                    Some(None)
+                } else {
+                    None
+                }
+            };
+            let idle = match next {
+                Some(idle) => idle,
+                None => match self.wait().await {
+                    Handoff::Client(client, created) => Some(IdleClient {
+                        client: *client,
+                        created,
+                        idle_since: Instant::now(),
+                    }),
+                    Handoff::Permit => None,
+                    Handoff::Closed => return Err(Error::Closed),
+                },
+            };
+
+            // Holding a slot in `size` from here on.
+            let slot = Slot::new(&self.inner);
+            match idle {
+                Some(idle) => {
+                    if let Some(client) = self.check(slot, idle).await {
+                        return Ok(client);
+                    }
+                }
+                None => return self.open(slot).await,
+            }
+        }
+    }
+
+    /// Validates an idle client, returning it as a guard or discarding it.
+    async fn check(&self, slot: Slot, idle: IdleClient) -> Option<PooledClient> {
+        let IdleClient {
+            mut client,
+            created,
+            idle_since,
+        } = idle;
+        if client.is_closed() || self.inner.options.expired(created, idle_since, true) {
+            return None;
+        }
+        if let Some(query) = &self.inner.options.test_query
+            && client.batch_execute(query).await.is_err()
+        {
+            return None;
+        }
+        Some(slot.fill(client, created))
+    }
+
+    async fn open(&self, slot: Slot) -> Result<PooledClient> {
+        let client = Client::connect_with_config(&self.inner.config).await?;
+        Ok(slot.fill(client, Instant::now()))
+    }
+
+    fn wait(&self) -> WaitFor {
+        let waiter = Rc::new(RefCell::new(Waiter::default()));
+        self.inner
+            .state
+            .borrow_mut()
+            .waiters
+            .push_back(waiter.clone());
+        WaitFor {
+            waiter,
+            pool: self.inner.clone(),
+        }
+    }
+
+    fn start_maintenance(&self) {
+        let options = &self.inner.options;
+        if options.idle_timeout.is_none() && options.max_lifetime.is_none() && options.min_size == 0
+        {
+            return;
+        }
+        let mut state = self.inner.state.borrow_mut();
+        if state.maintenance_started {
+            return;
+        }
+        state.maintenance_started = true;
+        monoio::spawn(maintain(Rc::downgrade(&self.inner)));
+    }
+}
+
+impl PoolOptions {
+    /// Whether a connection should be closed rather than reused. The idle
+    /// timeout is not applied to the connections that keep the pool at its
+    /// minimum size.
+    fn expired(&self, created: Instant, idle_since: Instant, above_min: bool) -> bool {
+        let now = Instant::now();
+        if self.max_lifetime.is_some_and(|max| now - created >= max) {
+            return true;
+        }
+        above_min && self.idle_timeout.is_some_and(|max| now - idle_since >= max)
+    }
+}
+
+impl PoolInner {
+    /// Takes a returned client back, handing it straight to the longest
+    /// waiter if there is one.
+    fn put(&self, mut client: Client, created: Instant) {
+        if client.is_closed() {
+            self.discard();
+            return;
+        }
+        if self.state.borrow().closed {
+            self.discard();
+            monoio::spawn(close_client(client));
+            return;
+        }
+        client.defer_rollback_if_open();
+        let mut state = self.state.borrow_mut();
+        match state.waiters.pop_front() {
+            Some(waiter) => {
+                drop(state);
+                self.hand_off(waiter, Handoff::Client(Box::new(client), created));
+            }
+            None => state.idle.push_back(IdleClient {
+                client,
+                created,
+                idle_since: Instant::now(),
+            }),
+        }
+    }
+
+    /// Forgets a connection, letting the longest waiter open a new one.
+    fn discard(&self) {
+        let mut state = self.state.borrow_mut();
+        match state.waiters.pop_front() {
+            Some(waiter) => {
+                drop(state);
+                self.hand_off(waiter, Handoff::Permit);
+            }
+            None => state.size -= 1,
+        }
+    }
+
+    fn hand_off(&self, waiter: Rc<RefCell<Waiter>>, handoff: Handoff) {
+        let mut waiter = waiter.borrow_mut();
+        waiter.handoff = Some(handoff);
+        if let Some(waker) = waiter.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A slot in the pool's `size`, held while a connection is being checked,
+/// opened or closed. Unless it is filled with a client, dropping it frees the
+/// slot, including when the future holding it is dropped, for example by an
+/// acquire timeout.
+struct Slot {
+    pool: Option<Rc<PoolInner>>,
+}
+
+impl Slot {
+    fn new(pool: &Rc<PoolInner>) -> Self {
         Self {
-            addr: addr.to_string(),
-            user: user.to_string(),
-            password: password.map(|s| s.to_string()),
-            database: database.map(|s| s.to_string()),
+            pool: Some(pool.clone()),
+        }
+    }
+
+    fn fill(mut self, client: Client, created: Instant) -> PooledClient {
+        PooledClient {
+            client: Some(client),
+            created,
+            pool: self.pool.take().unwrap(),
+        }
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.discard();
+        }
+    }
+}
+
+/// Waits for a [`Handoff`]. If dropped after receiving one, for example by
+/// an acquire timeout, it is passed on so nothing leaks.
+struct WaitFor {
+    waiter: Rc<RefCell<Waiter>>,
+    pool: Rc<PoolInner>,
+}
+
+impl Future for WaitFor {
+    type Output = Handoff;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Handoff> {
+        let mut waiter = self.waiter.borrow_mut();
+        match waiter.handoff.take() {
+            Some(handoff) => Poll::Ready(handoff),
+            None => {
+                waiter.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for WaitFor {
+    fn drop(&mut self) {
+        let handoff = self.waiter.borrow_mut().handoff.take();
+        match handoff {
+            Some(Handoff::Client(client, created)) => self.pool.put(*client, created),
+            Some(Handoff::Permit) => self.pool.discard(),
+            Some(Handoff::Closed) => {}
+            None => self
+                .pool
+                .state
+                .borrow_mut()
+                .waiters
+                .retain(|w| !Rc::ptr_eq(w, &self.waiter)),
         }
     }
+}
+
+/// Closes a connection the pool is done with, logging any failure.
+async fn close_client(client: Client) {
+    if let Err(e) = client.close().await {
+        tracing::debug!("failed to close pool connection: {}", e);
+    }
+}
+
+/// Closes idle connections that have expired and opens new ones to keep the
+/// pool at its minimum size. Stops once the pool is closed or dropped.
+async fn maintain(pool: Weak<PoolInner>) {
+    loop {
+        monoio::time::sleep(MAINTENANCE_INTERVAL).await;
+        let Some(pool) = pool.upgrade() else {
+            return;
+        };
+        if pool.state.borrow().closed {
+            return;
+        }
+
+        {
+            let mut state = pool.state.borrow_mut();
+            let min_size = pool.options.min_size;
+            let mut size = state.size;
+            let options = &pool.options;
+            state.idle.retain(|idle| {
+                let keep = !options.expired(idle.created, idle.idle_since, size > min_size);
+                if !keep {
+                    size -= 1;
+                }
+                keep
+            });
+            state.size = size;
+        }
 
-    pub async fn get(&self) -> Result<Client> {
-        if let Some(client) = POOL.with(|p| p.borrow_mut().pop_front()) {
-            return Ok(client);
+        while {
+            let mut state = pool.state.borrow_mut();
+            let grow = state.size < pool.options.min_size;
+            if grow {
+                state.size += 1;
+            }
+            grow
+        } {
+            match Client::connect_with_config(&pool.config).await {
+                Ok(client) => pool.put(client, Instant::now()),
+                Err(e) => {
+                    tracing::warn!("failed to open pool connection: {}", e);
+                    pool.discard();
+                    break;
+                }
+            }
         }
-        Client::connect(
-            &self.addr,
-            &self.user,
-            self.password.as_deref(),
-            self.database.as_deref(),
-        )
-        .await
     }
+}
 
-    pub fn put(&self, client: Client) {
-        POOL.with(|p| p.borrow_mut().push_back(client));
+/// Settings for a [`Pool`], created by [`Pool::builder`].
+pub struct PoolBuilder {
+    config: Config,
+    options: PoolOptions,
+}
+
+impl PoolBuilder {
+    /// Sets the most connections the pool will open. Defaults to 10.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.options.max_size = max_size;
+        self
+    }
+
+    /// Sets how many connections the pool keeps open even when idle. Defaults
+    /// to 0.
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.options.min_size = min_size;
+        self
+    }
+
+    /// Sets how long [`Pool::get`] waits, including the time spent opening a
+    /// new connection, before failing with [`Error::Timeout`]. By default it
+    /// waits forever.
+    pub fn acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.options.acquire_timeout = Some(timeout);
+        self
+    }
+
+    /// Closes connections that have been idle for this long.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.options.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Closes connections once they have been open for this long, even if
+    /// they are in use; a checked-out connection is closed when it is
+    /// returned.
+    pub fn max_lifetime(mut self, lifetime: Duration) -> Self {
+        self.options.max_lifetime = Some(lifetime);
+        self
+    }
+
+    /// Runs `query` on an idle connection before handing it out, and discards
+    /// the connection if it fails.
+    pub fn test_query(mut self, query: &str) -> Self {
+        self.options.test_query = Some(query.to_string());
+        self
+    }
+
+    pub fn build(self) -> Pool {
+        Pool {
+            inner: Rc::new(PoolInner {
+                config: self.config,
+                options: self.options,
+                state: RefCell::new(PoolState {
+                    idle: VecDeque::new(),
+                    size: 0,
+                    waiters: VecDeque::new(),
+                    maintenance_started: false,
+                    closed: false,
+                }),
+            }),
+        }
+    }
+}
+
+/// A client checked out of a [`Pool`]. It dereferences to [`Client`] and is
+/// returned to the pool when dropped.
+pub struct PooledClient {
+    client: Option<Client>,
+    created: Instant,
+    pool: Rc<PoolInner>,
+}
+
+impl PooledClient {
+    /// Takes the client out of the pool for good. The pool may open another
+    /// connection in its place.
+    pub fn detach(mut self) -> Client {
+        self.pool.discard();
+        self.client.take().unwrap()
+    }
+}
+
+impl Deref for PooledClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            if self
+                .pool
+                .options
+                .expired(self.created, Instant::now(), false)
+            {
+                self.pool.discard();
+            } else {
+                self.pool.put(client, self.created);
+            }
+        }
     }
 }
@@ -0,0 +1,65 @@
+use crate::client::{Column, Row};
+use crate::error::Result;
+use std::fmt;
+
+/// A column of a [`Row`], given either by position or by name.
+///
+/// This trait is sealed and implemented for `usize`, `str` and `String`. Names are
+/// matched exactly first and then ignoring ASCII case; if several columns
+/// share a name, the first one is used.
+pub trait RowIndex: sealed::Sealed + fmt::Display {}
+
+pub(crate) mod sealed {
+    use super::Column;
+
+    pub trait Sealed {
+        #[doc(hidden)]
+        fn index(&self, columns: &[Column]) -> Option<usize>;
+    }
+}
+
+impl RowIndex for usize {}
+
+impl sealed::Sealed for usize {
+    fn index(&self, columns: &[Column]) -> Option<usize> {
+        (*self < columns.len()).then_some(*self)
+    }
+}
+
+impl RowIndex for str {}
+
+impl sealed::Sealed for str {
+    fn index(&self, columns: &[Column]) -> Option<usize> {
+        columns.iter().position(|c| c.name == self).or_else(|| {
+            columns
+                .iter()
+                .position(|c| c.name.eq_ignore_ascii_case(self))
+        })
+    }
+}
+
+impl RowIndex for String {}
+
+impl sealed::Sealed for String {
+    fn index(&self, columns: &[Column]) -> Option<usize> {
+        self.as_str().index(columns)
+    }
+}
+
+impl<T: ?Sized + RowIndex> RowIndex for &T {}
+
+impl<T: ?Sized + sealed::Sealed> sealed::Sealed for &T {
+    fn index(&self, columns: &[Column]) -> Option<usize> {
+        T::index(*self, columns)
+    }
+}
+
+/// A type that can be built from a [`Row`].
+///
+/// With the `derive` feature, `#[derive(FromRow)]` implements it for structs
+/// with named fields, reading each field from the column of the same name.
+/// A field can read a differently named column with
+/// `#[from_row(rename = "...")]`.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self>;
+}
@@ -0,0 +1,146 @@
+use crate::client::{Column, CommandTag, Row};
+use crate::connection::{self, Connection};
+use crate::error::{Error, Result};
+use crate::statement::{Statement, ToStatement};
+use monoio::io::stream::Stream;
+use postgres_protocol::message::{backend, frontend};
+
+/// Rows of a query, read from the connection as they are requested.
+///
+/// Created by [`Client::query_stream`](crate::Client::query_stream) and
+/// [`Client::query_stream_bounded`](crate::Client::query_stream_bounded).
+/// Rows are pulled with [`Stream::next`]. A server error is returned as the
+/// last item, once the connection is ready for the next query.
+///
+/// Dropping the stream before it is exhausted is allowed; the rest of the
+/// response is read and discarded before the connection is used again.
+pub struct RowStream<'a> {
+    connection: &'a mut Connection,
+    statement: Statement,
+    max_rows: i32,
+    /// Whether the `Sync` ending the query has been sent.
+    synced: bool,
+    done: bool,
+    rows: u64,
+    tag: Option<CommandTag>,
+    error: Option<Error>,
+}
+
+impl<'a> RowStream<'a> {
+    pub(crate) async fn start<T>(
+        connection: &'a mut Connection,
+        statement: &T,
+        params: &[&(dyn postgres_types::ToSql + Sync)],
+        max_rows: i32,
+    ) -> Result<Self>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let statement = connection.start_query(statement, params, max_rows).await?;
+        Ok(Self {
+            connection,
+            statement,
+            max_rows,
+            synced: max_rows == 0,
+            done: false,
+            rows: 0,
+            tag: None,
+            error: None,
+        })
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        self.statement.columns()
+    }
+
+    /// Returns the command tag once every row has been read.
+    pub fn command_tag(&self) -> Option<&CommandTag> {
+        self.tag.as_ref()
+    }
+
+    async fn sync(&mut self) -> Result<()> {
+        if !self.synced {
+            self.synced = true;
+            self.connection.sync().await?;
+        }
+        Ok(())
+    }
+
+    /// Stops reading rows. The rest of the response is read and discarded
+    /// before the connection is used again, once the portal is closed with
+    /// `Sync` if that has not been sent yet.
+    fn abandon(&mut self) {
+        if !self.done {
+            self.done = true;
+            if !self.synced {
+                frontend::sync(self.connection.defer_drain());
+            }
+        }
+    }
+
+    async fn next_row(&mut self) -> Result<Option<Row>> {
+        loop {
+            let (msg, raw) = self.connection.recv().await?;
+            match msg {
+                backend::Message::DataRow(_) => {
+                    self.rows += 1;
+                    return connection::data_row(&raw, self.statement.column_list()).map(Some);
+                }
+                backend::Message::PortalSuspended => {
+                    self.connection.continue_portal(self.max_rows).await?;
+                }
+                backend::Message::CommandComplete(body) => {
+                    // After a suspension the server only counts the rows of
+                    // the final `Execute`, so report the total instead.
+                    self.tag = Some(match connection::command_tag(&body)? {
+                        CommandTag::Select(_) => CommandTag::Select(self.rows),
+                        tag => tag,
+                    });
+                    self.sync().await?;
+                }
+                backend::Message::EmptyQueryResponse => {
+                    self.sync().await?;
+                }
+                backend::Message::ErrorResponse(body) => {
+                    let e = connection::error_response(body)?;
+                    self.connection.evict_if_stale(&self.statement, &e);
+                    self.error = Some(e);
+                    self.sync().await?;
+                }
+                backend::Message::ReadyForQuery(_) => {
+                    self.done = true;
+                    return match self.error.take() {
+                        Some(e) => Err(e),
+                        None => Ok(None),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Stream for RowStream<'_> {
+    type Item = Result<Row>;
+
+    async fn next(&mut self) -> Option<Result<Row>> {
+        if self.done {
+            return None;
+        }
+        match self.next_row().await {
+            Ok(row) => row.map(Ok),
+            Err(e) => {
+                // A server error has already been read up to ReadyForQuery;
+                // after any other error the rest is left to be discarded.
+                self.abandon();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl Drop for RowStream<'_> {
+    fn drop(&mut self) {
+        self.abandon();
+    }
+}
@@ -0,0 +1,67 @@
+use crate::client::{Column, CommandTag};
+use crate::error::{Error, Result};
+use crate::row::RowIndex;
+use bytes::Bytes;
+use std::sync::Arc;
+
+/// The result of one statement run by
+/// [`Client::simple_query`](crate::Client::simple_query).
+#[derive(Debug)]
+pub struct SimpleQueryResult {
+    /// The columns of `rows`, empty for statements that return none.
+    pub columns: Arc<Vec<Column>>,
+    pub rows: Vec<SimpleQueryRow>,
+    /// The command tag, whose [`CommandTag::rows`] is the number of rows the
+    /// statement returned or touched.
+    pub tag: CommandTag,
+}
+
+/// A row returned by the simple query protocol, with every value in text
+/// form.
+#[derive(Debug)]
+pub struct SimpleQueryRow {
+    pub(crate) columns: Arc<Vec<Column>>,
+    pub(crate) data: Vec<Option<Bytes>>,
+}
+
+impl SimpleQueryRow {
+    /// Returns the row's columns.
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Returns the number of values in the row.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the text of a column, given by position or by name, or `None`
+    /// if it is NULL.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the column does not exist or is not valid UTF-8.
+    /// [`SimpleQueryRow::try_get`] returns an error instead.
+    pub fn get<I: RowIndex>(&self, idx: I) -> Option<&str> {
+        match self.try_get(&idx) {
+            Ok(value) => value,
+            Err(e) => panic!("error retrieving column {}: {}", idx, e),
+        }
+    }
+
+    /// Returns the text of a column, given by position or by name, or `None`
+    /// if it is NULL.
+    pub fn try_get<I: RowIndex>(&self, idx: I) -> Result<Option<&str>> {
+        let index = idx
+            .index(&self.columns)
+            .ok_or_else(|| Error::Parse(format!("column {} not found", idx)))?;
+        self.data[index]
+            .as_deref()
+            .map(|b| std::str::from_utf8(b).map_err(|e| Error::Parse(e.to_string())))
+            .transpose()
+    }
+}
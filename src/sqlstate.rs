@@ -0,0 +1,1070 @@
+//! SQLSTATE error codes reported by the server.
+//!
+//! The variants are generated from PostgreSQL's `errcodes.txt`.
+
+/// A SQLSTATE error code.
+///
+/// Codes that are not known to this version of the crate are preserved in
+/// [`SqlState::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SqlState {
+    /// `00000` (successful_completion)
+    SuccessfulCompletion,
+    /// `01000` (warning)
+    Warning,
+    /// `0100C` (dynamic_result_sets_returned)
+    DynamicResultSetsReturned,
+    /// `01008` (implicit_zero_bit_padding)
+    ImplicitZeroBitPadding,
+    /// `01003` (null_value_eliminated_in_set_function)
+    NullValueEliminatedInSetFunction,
+    /// `01007` (privilege_not_granted)
+    PrivilegeNotGranted,
+    /// `01006` (privilege_not_revoked)
+    PrivilegeNotRevoked,
+    /// `01004` (string_data_right_truncation)
+    WarningStringDataRightTruncation,
+    /// `01P01` (deprecated_feature)
+    DeprecatedFeature,
+    /// `02000` (no_data)
+    NoData,
+    /// `02001` (no_additional_dynamic_result_sets_returned)
+    NoAdditionalDynamicResultSetsReturned,
+    /// `03000` (sql_statement_not_yet_complete)
+    SqlStatementNotYetComplete,
+    /// `08000` (connection_exception)
+    ConnectionException,
+    /// `08003` (connection_does_not_exist)
+    ConnectionDoesNotExist,
+    /// `08006` (connection_failure)
+    ConnectionFailure,
+    /// `08001` (sqlclient_unable_to_establish_sqlconnection)
+    SqlclientUnableToEstablishSqlconnection,
+    /// `08004` (sqlserver_rejected_establishment_of_sqlconnection)
+    SqlserverRejectedEstablishmentOfSqlconnection,
+    /// `08007` (transaction_resolution_unknown)
+    TransactionResolutionUnknown,
+    /// `08P01` (protocol_violation)
+    ProtocolViolation,
+    /// `09000` (triggered_action_exception)
+    TriggeredActionException,
+    /// `0A000` (feature_not_supported)
+    FeatureNotSupported,
+    /// `0B000` (invalid_transaction_initiation)
+    InvalidTransactionInitiation,
+    /// `0F000` (locator_exception)
+    LocatorException,
+    /// `0F001` (invalid_locator_specification)
+    InvalidLocatorSpecification,
+    /// `0L000` (invalid_grantor)
+    InvalidGrantor,
+    /// `0LP01` (invalid_grant_operation)
+    InvalidGrantOperation,
+    /// `0P000` (invalid_role_specification)
+    InvalidRoleSpecification,
+    /// `0Z000` (diagnostics_exception)
+    DiagnosticsException,
+    /// `0Z002` (stacked_diagnostics_accessed_without_active_handler)
+    StackedDiagnosticsAccessedWithoutActiveHandler,
+    /// `20000` (case_not_found)
+    CaseNotFound,
+    /// `21000` (cardinality_violation)
+    CardinalityViolation,
+    /// `22000` (data_exception)
+    DataException,
+    /// `2202E` (array_subscript_error)
+    ArraySubscriptError,
+    /// `22021` (character_not_in_repertoire)
+    CharacterNotInRepertoire,
+    /// `22008` (datetime_field_overflow)
+    DatetimeFieldOverflow,
+    /// `22012` (division_by_zero)
+    DivisionByZero,
+    /// `22005` (error_in_assignment)
+    ErrorInAssignment,
+    /// `2200B` (escape_character_conflict)
+    EscapeCharacterConflict,
+    /// `22022` (indicator_overflow)
+    IndicatorOverflow,
+    /// `22015` (interval_field_overflow)
+    IntervalFieldOverflow,
+    /// `2201E` (invalid_argument_for_logarithm)
+    InvalidArgumentForLogarithm,
+    /// `22014` (invalid_argument_for_ntile_function)
+    InvalidArgumentForNtileFunction,
+    /// `22016` (invalid_argument_for_nth_value_function)
+    InvalidArgumentForNthValueFunction,
+    /// `2201F` (invalid_argument_for_power_function)
+    InvalidArgumentForPowerFunction,
+    /// `2201G` (invalid_argument_for_width_bucket_function)
+    InvalidArgumentForWidthBucketFunction,
+    /// `22018` (invalid_character_value_for_cast)
+    InvalidCharacterValueForCast,
+    /// `22007` (invalid_datetime_format)
+    InvalidDatetimeFormat,
+    /// `22019` (invalid_escape_character)
+    InvalidEscapeCharacter,
+    /// `2200D` (invalid_escape_octet)
+    InvalidEscapeOctet,
+    /// `22025` (invalid_escape_sequence)
+    InvalidEscapeSequence,
+    /// `22P06` (nonstandard_use_of_escape_character)
+    NonstandardUseOfEscapeCharacter,
+    /// `22010` (invalid_indicator_parameter_value)
+    InvalidIndicatorParameterValue,
+    /// `22023` (invalid_parameter_value)
+    InvalidParameterValue,
+    /// `22013` (invalid_preceding_or_following_size)
+    InvalidPrecedingOrFollowingSize,
+    /// `2201B` (invalid_regular_expression)
+    InvalidRegularExpression,
+    /// `2201W` (invalid_row_count_in_limit_clause)
+    InvalidRowCountInLimitClause,
+    /// `2201X` (invalid_row_count_in_result_offset_clause)
+    InvalidRowCountInResultOffsetClause,
+    /// `2202H` (invalid_tablesample_argument)
+    InvalidTablesampleArgument,
+    /// `2202G` (invalid_tablesample_repeat)
+    InvalidTablesampleRepeat,
+    /// `22009` (invalid_time_zone_displacement_value)
+    InvalidTimeZoneDisplacementValue,
+    /// `2200C` (invalid_use_of_escape_character)
+    InvalidUseOfEscapeCharacter,
+    /// `2200G` (most_specific_type_mismatch)
+    MostSpecificTypeMismatch,
+    /// `22004` (null_value_not_allowed)
+    NullValueNotAllowed,
+    /// `22002` (null_value_no_indicator_parameter)
+    NullValueNoIndicatorParameter,
+    /// `22003` (numeric_value_out_of_range)
+    NumericValueOutOfRange,
+    /// `2200H` (sequence_generator_limit_exceeded)
+    SequenceGeneratorLimitExceeded,
+    /// `22026` (string_data_length_mismatch)
+    StringDataLengthMismatch,
+    /// `22001` (string_data_right_truncation)
+    StringDataRightTruncation,
+    /// `22011` (substring_error)
+    SubstringError,
+    /// `22027` (trim_error)
+    TrimError,
+    /// `22024` (unterminated_c_string)
+    UnterminatedCString,
+    /// `2200F` (zero_length_character_string)
+    ZeroLengthCharacterString,
+    /// `22P01` (floating_point_exception)
+    FloatingPointException,
+    /// `22P02` (invalid_text_representation)
+    InvalidTextRepresentation,
+    /// `22P03` (invalid_binary_representation)
+    InvalidBinaryRepresentation,
+    /// `22P04` (bad_copy_file_format)
+    BadCopyFileFormat,
+    /// `22P05` (untranslatable_character)
+    UntranslatableCharacter,
+    /// `2200L` (not_an_xml_document)
+    NotAnXmlDocument,
+    /// `2200M` (invalid_xml_document)
+    InvalidXmlDocument,
+    /// `2200N` (invalid_xml_content)
+    InvalidXmlContent,
+    /// `2200S` (invalid_xml_comment)
+    InvalidXmlComment,
+    /// `2200T` (invalid_xml_processing_instruction)
+    InvalidXmlProcessingInstruction,
+    /// `22030` (duplicate_json_object_key_value)
+    DuplicateJsonObjectKeyValue,
+    /// `22031` (invalid_argument_for_sql_json_datetime_function)
+    InvalidArgumentForSqlJsonDatetimeFunction,
+    /// `22032` (invalid_json_text)
+    InvalidJsonText,
+    /// `22033` (invalid_sql_json_subscript)
+    InvalidSqlJsonSubscript,
+    /// `22034` (more_than_one_sql_json_item)
+    MoreThanOneSqlJsonItem,
+    /// `22035` (no_sql_json_item)
+    NoSqlJsonItem,
+    /// `22036` (non_numeric_sql_json_item)
+    NonNumericSqlJsonItem,
+    /// `22037` (non_unique_keys_in_a_json_object)
+    NonUniqueKeysInAJsonObject,
+    /// `22038` (singleton_sql_json_item_required)
+    SingletonSqlJsonItemRequired,
+    /// `22039` (sql_json_array_not_found)
+    SqlJsonArrayNotFound,
+    /// `2203A` (sql_json_member_not_found)
+    SqlJsonMemberNotFound,
+    /// `2203B` (sql_json_number_not_found)
+    SqlJsonNumberNotFound,
+    /// `2203C` (sql_json_object_not_found)
+    SqlJsonObjectNotFound,
+    /// `2203D` (too_many_json_array_elements)
+    TooManyJsonArrayElements,
+    /// `2203E` (too_many_json_object_members)
+    TooManyJsonObjectMembers,
+    /// `2203F` (sql_json_scalar_required)
+    SqlJsonScalarRequired,
+    /// `2203G` (sql_json_item_cannot_be_cast_to_target_type)
+    SqlJsonItemCannotBeCastToTargetType,
+    /// `23000` (integrity_constraint_violation)
+    IntegrityConstraintViolation,
+    /// `23001` (restrict_violation)
+    RestrictViolation,
+    /// `23502` (not_null_violation)
+    NotNullViolation,
+    /// `23503` (foreign_key_violation)
+    ForeignKeyViolation,
+    /// `23505` (unique_violation)
+    UniqueViolation,
+    /// `23514` (check_violation)
+    CheckViolation,
+    /// `23P01` (exclusion_violation)
+    ExclusionViolation,
+    /// `24000` (invalid_cursor_state)
+    InvalidCursorState,
+    /// `25000` (invalid_transaction_state)
+    InvalidTransactionState,
+    /// `25001` (active_sql_transaction)
+    ActiveSqlTransaction,
+    /// `25002` (branch_transaction_already_active)
+    BranchTransactionAlreadyActive,
+    /// `25008` (held_cursor_requires_same_isolation_level)
+    HeldCursorRequiresSameIsolationLevel,
+    /// `25003` (inappropriate_access_mode_for_branch_transaction)
+    InappropriateAccessModeForBranchTransaction,
+    /// `25004` (inappropriate_isolation_level_for_branch_transaction)
+    InappropriateIsolationLevelForBranchTransaction,
+    /// `25005` (no_active_sql_transaction_for_branch_transaction)
+    NoActiveSqlTransactionForBranchTransaction,
+    /// `25006` (read_only_sql_transaction)
+    ReadOnlySqlTransaction,
+    /// `25007` (schema_and_data_statement_mixing_not_supported)
+    SchemaAndDataStatementMixingNotSupported,
+    /// `25P01` (no_active_sql_transaction)
+    NoActiveSqlTransaction,
+    /// `25P02` (in_failed_sql_transaction)
+    InFailedSqlTransaction,
+    /// `25P03` (idle_in_transaction_session_timeout)
+    IdleInTransactionSessionTimeout,
+    /// `26000` (invalid_sql_statement_name)
+    InvalidSqlStatementName,
+    /// `27000` (triggered_data_change_violation)
+    TriggeredDataChangeViolation,
+    /// `28000` (invalid_authorization_specification)
+    InvalidAuthorizationSpecification,
+    /// `28P01` (invalid_password)
+    InvalidPassword,
+    /// `2B000` (dependent_privilege_descriptors_still_exist)
+    DependentPrivilegeDescriptorsStillExist,
+    /// `2BP01` (dependent_objects_still_exist)
+    DependentObjectsStillExist,
+    /// `2D000` (invalid_transaction_termination)
+    InvalidTransactionTermination,
+    /// `2F000` (sql_routine_exception)
+    SqlRoutineException,
+    /// `2F005` (function_executed_no_return_statement)
+    FunctionExecutedNoReturnStatement,
+    /// `2F002` (modifying_sql_data_not_permitted)
+    SREModifyingSqlDataNotPermitted,
+    /// `2F003` (prohibited_sql_statement_attempted)
+    SREProhibitedSqlStatementAttempted,
+    /// `2F004` (reading_sql_data_not_permitted)
+    SREReadingSqlDataNotPermitted,
+    /// `34000` (invalid_cursor_name)
+    InvalidCursorName,
+    /// `38000` (external_routine_exception)
+    ExternalRoutineException,
+    /// `38001` (containing_sql_not_permitted)
+    ContainingSqlNotPermitted,
+    /// `38002` (modifying_sql_data_not_permitted)
+    EREModifyingSqlDataNotPermitted,
+    /// `38003` (prohibited_sql_statement_attempted)
+    EREProhibitedSqlStatementAttempted,
+    /// `38004` (reading_sql_data_not_permitted)
+    EREReadingSqlDataNotPermitted,
+    /// `39000` (external_routine_invocation_exception)
+    ExternalRoutineInvocationException,
+    /// `39001` (invalid_sqlstate_returned)
+    InvalidSqlstateReturned,
+    /// `39004` (null_value_not_allowed)
+    ERIENullValueNotAllowed,
+    /// `39P01` (trigger_protocol_violated)
+    TriggerProtocolViolated,
+    /// `39P02` (srf_protocol_violated)
+    SrfProtocolViolated,
+    /// `39P03` (event_trigger_protocol_violated)
+    EventTriggerProtocolViolated,
+    /// `3B000` (savepoint_exception)
+    SavepointException,
+    /// `3B001` (invalid_savepoint_specification)
+    InvalidSavepointSpecification,
+    /// `3D000` (invalid_catalog_name)
+    InvalidCatalogName,
+    /// `3F000` (invalid_schema_name)
+    InvalidSchemaName,
+    /// `40000` (transaction_rollback)
+    TransactionRollback,
+    /// `40002` (transaction_integrity_constraint_violation)
+    TransactionIntegrityConstraintViolation,
+    /// `40001` (serialization_failure)
+    SerializationFailure,
+    /// `40003` (statement_completion_unknown)
+    StatementCompletionUnknown,
+    /// `40P01` (deadlock_detected)
+    DeadlockDetected,
+    /// `42000` (syntax_error_or_access_rule_violation)
+    SyntaxErrorOrAccessRuleViolation,
+    /// `42601` (syntax_error)
+    SyntaxError,
+    /// `42501` (insufficient_privilege)
+    InsufficientPrivilege,
+    /// `42846` (cannot_coerce)
+    CannotCoerce,
+    /// `42803` (grouping_error)
+    GroupingError,
+    /// `42P20` (windowing_error)
+    WindowingError,
+    /// `42P19` (invalid_recursion)
+    InvalidRecursion,
+    /// `42830` (invalid_foreign_key)
+    InvalidForeignKey,
+    /// `42602` (invalid_name)
+    InvalidName,
+    /// `42622` (name_too_long)
+    NameTooLong,
+    /// `42939` (reserved_name)
+    ReservedName,
+    /// `42804` (datatype_mismatch)
+    DatatypeMismatch,
+    /// `42P18` (indeterminate_datatype)
+    IndeterminateDatatype,
+    /// `42P21` (collation_mismatch)
+    CollationMismatch,
+    /// `42P22` (indeterminate_collation)
+    IndeterminateCollation,
+    /// `42809` (wrong_object_type)
+    WrongObjectType,
+    /// `428C9` (generated_always)
+    GeneratedAlways,
+    /// `42703` (undefined_column)
+    UndefinedColumn,
+    /// `42883` (undefined_function)
+    UndefinedFunction,
+    /// `42P01` (undefined_table)
+    UndefinedTable,
+    /// `42P02` (undefined_parameter)
+    UndefinedParameter,
+    /// `42704` (undefined_object)
+    UndefinedObject,
+    /// `42701` (duplicate_column)
+    DuplicateColumn,
+    /// `42P03` (duplicate_cursor)
+    DuplicateCursor,
+    /// `42P04` (duplicate_database)
+    DuplicateDatabase,
+    /// `42723` (duplicate_function)
+    DuplicateFunction,
+    /// `42P05` (duplicate_prepared_statement)
+    DuplicatePreparedStatement,
+    /// `42P06` (duplicate_schema)
+    DuplicateSchema,
+    /// `42P07` (duplicate_table)
+    DuplicateTable,
+    /// `42712` (duplicate_alias)
+    DuplicateAlias,
+    /// `42710` (duplicate_object)
+    DuplicateObject,
+    /// `42702` (ambiguous_column)
+    AmbiguousColumn,
+    /// `42725` (ambiguous_function)
+    AmbiguousFunction,
+    /// `42P08` (ambiguous_parameter)
+    AmbiguousParameter,
+    /// `42P09` (ambiguous_alias)
+    AmbiguousAlias,
+    /// `42P10` (invalid_column_reference)
+    InvalidColumnReference,
+    /// `42611` (invalid_column_definition)
+    InvalidColumnDefinition,
+    /// `42P11` (invalid_cursor_definition)
+    InvalidCursorDefinition,
+    /// `42P12` (invalid_database_definition)
+    InvalidDatabaseDefinition,
+    /// `42P13` (invalid_function_definition)
+    InvalidFunctionDefinition,
+    /// `42P14` (invalid_prepared_statement_definition)
+    InvalidPreparedStatementDefinition,
+    /// `42P15` (invalid_schema_definition)
+    InvalidSchemaDefinition,
+    /// `42P16` (invalid_table_definition)
+    InvalidTableDefinition,
+    /// `42P17` (invalid_object_definition)
+    InvalidObjectDefinition,
+    /// `44000` (with_check_option_violation)
+    WithCheckOptionViolation,
+    /// `53000` (insufficient_resources)
+    InsufficientResources,
+    /// `53100` (disk_full)
+    DiskFull,
+    /// `53200` (out_of_memory)
+    OutOfMemory,
+    /// `53300` (too_many_connections)
+    TooManyConnections,
+    /// `53400` (configuration_limit_exceeded)
+    ConfigurationLimitExceeded,
+    /// `54000` (program_limit_exceeded)
+    ProgramLimitExceeded,
+    /// `54001` (statement_too_complex)
+    StatementTooComplex,
+    /// `54011` (too_many_columns)
+    TooManyColumns,
+    /// `54023` (too_many_arguments)
+    TooManyArguments,
+    /// `55000` (object_not_in_prerequisite_state)
+    ObjectNotInPrerequisiteState,
+    /// `55006` (object_in_use)
+    ObjectInUse,
+    /// `55P02` (cant_change_runtime_param)
+    CantChangeRuntimeParam,
+    /// `55P03` (lock_not_available)
+    LockNotAvailable,
+    /// `55P04` (unsafe_new_enum_value_usage)
+    UnsafeNewEnumValueUsage,
+    /// `57000` (operator_intervention)
+    OperatorIntervention,
+    /// `57014` (query_canceled)
+    QueryCanceled,
+    /// `57P01` (admin_shutdown)
+    AdminShutdown,
+    /// `57P02` (crash_shutdown)
+    CrashShutdown,
+    /// `57P03` (cannot_connect_now)
+    CannotConnectNow,
+    /// `57P04` (database_dropped)
+    DatabaseDropped,
+    /// `57P05` (idle_session_timeout)
+    IdleSessionTimeout,
+    /// `58000` (system_error)
+    SystemError,
+    /// `58030` (io_error)
+    IoError,
+    /// `58P01` (undefined_file)
+    UndefinedFile,
+    /// `58P02` (duplicate_file)
+    DuplicateFile,
+    /// `72000` (snapshot_too_old)
+    SnapshotTooOld,
+    /// `F0000` (config_file_error)
+    ConfigFileError,
+    /// `F0001` (lock_file_exists)
+    LockFileExists,
+    /// `HV000` (fdw_error)
+    FdwError,
+    /// `HV005` (fdw_column_name_not_found)
+    FdwColumnNameNotFound,
+    /// `HV002` (fdw_dynamic_parameter_value_needed)
+    FdwDynamicParameterValueNeeded,
+    /// `HV010` (fdw_function_sequence_error)
+    FdwFunctionSequenceError,
+    /// `HV021` (fdw_inconsistent_descriptor_information)
+    FdwInconsistentDescriptorInformation,
+    /// `HV024` (fdw_invalid_attribute_value)
+    FdwInvalidAttributeValue,
+    /// `HV007` (fdw_invalid_column_name)
+    FdwInvalidColumnName,
+    /// `HV008` (fdw_invalid_column_number)
+    FdwInvalidColumnNumber,
+    /// `HV004` (fdw_invalid_data_type)
+    FdwInvalidDataType,
+    /// `HV006` (fdw_invalid_data_type_descriptors)
+    FdwInvalidDataTypeDescriptors,
+    /// `HV091` (fdw_invalid_descriptor_field_identifier)
+    FdwInvalidDescriptorFieldIdentifier,
+    /// `HV00B` (fdw_invalid_handle)
+    FdwInvalidHandle,
+    /// `HV00C` (fdw_invalid_option_index)
+    FdwInvalidOptionIndex,
+    /// `HV00D` (fdw_invalid_option_name)
+    FdwInvalidOptionName,
+    /// `HV090` (fdw_invalid_string_length_or_buffer_length)
+    FdwInvalidStringLengthOrBufferLength,
+    /// `HV00A` (fdw_invalid_string_format)
+    FdwInvalidStringFormat,
+    /// `HV009` (fdw_invalid_use_of_null_pointer)
+    FdwInvalidUseOfNullPointer,
+    /// `HV014` (fdw_too_many_handles)
+    FdwTooManyHandles,
+    /// `HV001` (fdw_out_of_memory)
+    FdwOutOfMemory,
+    /// `HV00P` (fdw_no_schemas)
+    FdwNoSchemas,
+    /// `HV00J` (fdw_option_name_not_found)
+    FdwOptionNameNotFound,
+    /// `HV00K` (fdw_reply_handle)
+    FdwReplyHandle,
+    /// `HV00Q` (fdw_schema_not_found)
+    FdwSchemaNotFound,
+    /// `HV00R` (fdw_table_not_found)
+    FdwTableNotFound,
+    /// `HV00L` (fdw_unable_to_create_execution)
+    FdwUnableToCreateExecution,
+    /// `HV00M` (fdw_unable_to_create_reply)
+    FdwUnableToCreateReply,
+    /// `HV00N` (fdw_unable_to_establish_connection)
+    FdwUnableToEstablishConnection,
+    /// `P0000` (plpgsql_error)
+    PlpgsqlError,
+    /// `P0001` (raise_exception)
+    RaiseException,
+    /// `P0002` (no_data_found)
+    NoDataFound,
+    /// `P0003` (too_many_rows)
+    TooManyRows,
+    /// `P0004` (assert_failure)
+    AssertFailure,
+    /// `XX000` (internal_error)
+    InternalError,
+    /// `XX001` (data_corrupted)
+    DataCorrupted,
+    /// `XX002` (index_corrupted)
+    IndexCorrupted,
+    /// Any code not listed above.
+    Other(String),
+}
+
+impl SqlState {
+    /// Parses a five-character SQLSTATE code.
+    pub fn from_code(code: &str) -> SqlState {
+        match code {
+            "00000" => SqlState::SuccessfulCompletion,
+            "01000" => SqlState::Warning,
+            "0100C" => SqlState::DynamicResultSetsReturned,
+            "01008" => SqlState::ImplicitZeroBitPadding,
+            "01003" => SqlState::NullValueEliminatedInSetFunction,
+            "01007" => SqlState::PrivilegeNotGranted,
+            "01006" => SqlState::PrivilegeNotRevoked,
+            "01004" => SqlState::WarningStringDataRightTruncation,
+            "01P01" => SqlState::DeprecatedFeature,
+            "02000" => SqlState::NoData,
+            "02001" => SqlState::NoAdditionalDynamicResultSetsReturned,
+            "03000" => SqlState::SqlStatementNotYetComplete,
+            "08000" => SqlState::ConnectionException,
+            "08003" => SqlState::ConnectionDoesNotExist,
+            "08006" => SqlState::ConnectionFailure,
+            "08001" => SqlState::SqlclientUnableToEstablishSqlconnection,
+            "08004" => SqlState::SqlserverRejectedEstablishmentOfSqlconnection,
+            "08007" => SqlState::TransactionResolutionUnknown,
+            "08P01" => SqlState::ProtocolViolation,
+            "09000" => SqlState::TriggeredActionException,
+            "0A000" => SqlState::FeatureNotSupported,
+            "0B000" => SqlState::InvalidTransactionInitiation,
+            "0F000" => SqlState::LocatorException,
+            "0F001" => SqlState::InvalidLocatorSpecification,
+            "0L000" => SqlState::InvalidGrantor,
+            "0LP01" => SqlState::InvalidGrantOperation,
+            "0P000" => SqlState::InvalidRoleSpecification,
+            "0Z000" => SqlState::DiagnosticsException,
+            "0Z002" => SqlState::StackedDiagnosticsAccessedWithoutActiveHandler,
+            "20000" => SqlState::CaseNotFound,
+            "21000" => SqlState::CardinalityViolation,
+            "22000" => SqlState::DataException,
+            "2202E" => SqlState::ArraySubscriptError,
+            "22021" => SqlState::CharacterNotInRepertoire,
+            "22008" => SqlState::DatetimeFieldOverflow,
+            "22012" => SqlState::DivisionByZero,
+            "22005" => SqlState::ErrorInAssignment,
+            "2200B" => SqlState::EscapeCharacterConflict,
+            "22022" => SqlState::IndicatorOverflow,
+            "22015" => SqlState::IntervalFieldOverflow,
+            "2201E" => SqlState::InvalidArgumentForLogarithm,
+            "22014" => SqlState::InvalidArgumentForNtileFunction,
+            "22016" => SqlState::InvalidArgumentForNthValueFunction,
+            "2201F" => SqlState::InvalidArgumentForPowerFunction,
+            "2201G" => SqlState::InvalidArgumentForWidthBucketFunction,
+            "22018" => SqlState::InvalidCharacterValueForCast,
+            "22007" => SqlState::InvalidDatetimeFormat,
+            "22019" => SqlState::InvalidEscapeCharacter,
+            "2200D" => SqlState::InvalidEscapeOctet,
+            "22025" => SqlState::InvalidEscapeSequence,
+            "22P06" => SqlState::NonstandardUseOfEscapeCharacter,
+            "22010" => SqlState::InvalidIndicatorParameterValue,
+            "22023" => SqlState::InvalidParameterValue,
+            "22013" => SqlState::InvalidPrecedingOrFollowingSize,
+            "2201B" => SqlState::InvalidRegularExpression,
+            "2201W" => SqlState::InvalidRowCountInLimitClause,
+            "2201X" => SqlState::InvalidRowCountInResultOffsetClause,
+            "2202H" => SqlState::InvalidTablesampleArgument,
+            "2202G" => SqlState::InvalidTablesampleRepeat,
+            "22009" => SqlState::InvalidTimeZoneDisplacementValue,
+            "2200C" => SqlState::InvalidUseOfEscapeCharacter,
+            "2200G" => SqlState::MostSpecificTypeMismatch,
+            "22004" => SqlState::NullValueNotAllowed,
+            "22002" => SqlState::NullValueNoIndicatorParameter,
+            "22003" => SqlState::NumericValueOutOfRange,
+            "2200H" => SqlState::SequenceGeneratorLimitExceeded,
+            "22026" => SqlState::StringDataLengthMismatch,
+            "22001" => SqlState::StringDataRightTruncation,
+            "22011" => SqlState::SubstringError,
+            "22027" => SqlState::TrimError,
+            "22024" => SqlState::UnterminatedCString,
+            "2200F" => SqlState::ZeroLengthCharacterString,
+            "22P01" => SqlState::FloatingPointException,
+            "22P02" => SqlState::InvalidTextRepresentation,
+            "22P03" => SqlState::InvalidBinaryRepresentation,
+            "22P04" => SqlState::BadCopyFileFormat,
+            "22P05" => SqlState::UntranslatableCharacter,
+            "2200L" => SqlState::NotAnXmlDocument,
+            "2200M" => SqlState::InvalidXmlDocument,
+            "2200N" => SqlState::InvalidXmlContent,
+            "2200S" => SqlState::InvalidXmlComment,
+            "2200T" => SqlState::InvalidXmlProcessingInstruction,
+            "22030" => SqlState::DuplicateJsonObjectKeyValue,
+            "22031" => SqlState::InvalidArgumentForSqlJsonDatetimeFunction,
+            "22032" => SqlState::InvalidJsonText,
+            "22033" => SqlState::InvalidSqlJsonSubscript,
+            "22034" => SqlState::MoreThanOneSqlJsonItem,
+            "22035" => SqlState::NoSqlJsonItem,
+            "22036" => SqlState::NonNumericSqlJsonItem,
+            "22037" => SqlState::NonUniqueKeysInAJsonObject,
+            "22038" => SqlState::SingletonSqlJsonItemRequired,
+            "22039" => SqlState::SqlJsonArrayNotFound,
+            "2203A" => SqlState::SqlJsonMemberNotFound,
+            "2203B" => SqlState::SqlJsonNumberNotFound,
+            "2203C" => SqlState::SqlJsonObjectNotFound,
+            "2203D" => SqlState::TooManyJsonArrayElements,
+            "2203E" => SqlState::TooManyJsonObjectMembers,
+            "2203F" => SqlState::SqlJsonScalarRequired,
+            "2203G" => SqlState::SqlJsonItemCannotBeCastToTargetType,
+            "23000" => SqlState::IntegrityConstraintViolation,
+            "23001" => SqlState::RestrictViolation,
+            "23502" => SqlState::NotNullViolation,
+            "23503" => SqlState::ForeignKeyViolation,
+            "23505" => SqlState::UniqueViolation,
+            "23514" => SqlState::CheckViolation,
+            "23P01" => SqlState::ExclusionViolation,
+            "24000" => SqlState::InvalidCursorState,
+            "25000" => SqlState::InvalidTransactionState,
+            "25001" => SqlState::ActiveSqlTransaction,
+            "25002" => SqlState::BranchTransactionAlreadyActive,
+            "25008" => SqlState::HeldCursorRequiresSameIsolationLevel,
+            "25003" => SqlState::InappropriateAccessModeForBranchTransaction,
+            "25004" => SqlState::InappropriateIsolationLevelForBranchTransaction,
+            "25005" => SqlState::NoActiveSqlTransactionForBranchTransaction,
+            "25006" => SqlState::ReadOnlySqlTransaction,
+            "25007" => SqlState::SchemaAndDataStatementMixingNotSupported,
+            "25P01" => SqlState::NoActiveSqlTransaction,
+            "25P02" => SqlState::InFailedSqlTransaction,
+            "25P03" => SqlState::IdleInTransactionSessionTimeout,
+            "26000" => SqlState::InvalidSqlStatementName,
+            "27000" => SqlState::TriggeredDataChangeViolation,
+            "28000" => SqlState::InvalidAuthorizationSpecification,
+            "28P01" => SqlState::InvalidPassword,
+            "2B000" => SqlState::DependentPrivilegeDescriptorsStillExist,
+            "2BP01" => SqlState::DependentObjectsStillExist,
+            "2D000" => SqlState::InvalidTransactionTermination,
+            "2F000" => SqlState::SqlRoutineException,
+            "2F005" => SqlState::FunctionExecutedNoReturnStatement,
+            "2F002" => SqlState::SREModifyingSqlDataNotPermitted,
+            "2F003" => SqlState::SREProhibitedSqlStatementAttempted,
+            "2F004" => SqlState::SREReadingSqlDataNotPermitted,
+            "34000" => SqlState::InvalidCursorName,
+            "38000" => SqlState::ExternalRoutineException,
+            "38001" => SqlState::ContainingSqlNotPermitted,
+            "38002" => SqlState::EREModifyingSqlDataNotPermitted,
+            "38003" => SqlState::EREProhibitedSqlStatementAttempted,
+            "38004" => SqlState::EREReadingSqlDataNotPermitted,
+            "39000" => SqlState::ExternalRoutineInvocationException,
+            "39001" => SqlState::InvalidSqlstateReturned,
+            "39004" => SqlState::ERIENullValueNotAllowed,
+            "39P01" => SqlState::TriggerProtocolViolated,
+            "39P02" => SqlState::SrfProtocolViolated,
+            "39P03" => SqlState::EventTriggerProtocolViolated,
+            "3B000" => SqlState::SavepointException,
+            "3B001" => SqlState::InvalidSavepointSpecification,
+            "3D000" => SqlState::InvalidCatalogName,
+            "3F000" => SqlState::InvalidSchemaName,
+            "40000" => SqlState::TransactionRollback,
+            "40002" => SqlState::TransactionIntegrityConstraintViolation,
+            "40001" => SqlState::SerializationFailure,
+            "40003" => SqlState::StatementCompletionUnknown,
+            "40P01" => SqlState::DeadlockDetected,
+            "42000" => SqlState::SyntaxErrorOrAccessRuleViolation,
+            "42601" => SqlState::SyntaxError,
+            "42501" => SqlState::InsufficientPrivilege,
+            "42846" => SqlState::CannotCoerce,
+            "42803" => SqlState::GroupingError,
+            "42P20" => SqlState::WindowingError,
+            "42P19" => SqlState::InvalidRecursion,
+            "42830" => SqlState::InvalidForeignKey,
+            "42602" => SqlState::InvalidName,
+            "42622" => SqlState::NameTooLong,
+            "42939" => SqlState::ReservedName,
+            "42804" => SqlState::DatatypeMismatch,
+            "42P18" => SqlState::IndeterminateDatatype,
+            "42P21" => SqlState::CollationMismatch,
+            "42P22" => SqlState::IndeterminateCollation,
+            "42809" => SqlState::WrongObjectType,
+            "428C9" => SqlState::GeneratedAlways,
+            "42703" => SqlState::UndefinedColumn,
+            "42883" => SqlState::UndefinedFunction,
+            "42P01" => SqlState::UndefinedTable,
+            "42P02" => SqlState::UndefinedParameter,
+            "42704" => SqlState::UndefinedObject,
+            "42701" => SqlState::DuplicateColumn,
+            "42P03" => SqlState::DuplicateCursor,
+            "42P04" => SqlState::DuplicateDatabase,
+            "42723" => SqlState::DuplicateFunction,
+            "42P05" => SqlState::DuplicatePreparedStatement,
+            "42P06" => SqlState::DuplicateSchema,
+            "42P07" => SqlState::DuplicateTable,
+            "42712" => SqlState::DuplicateAlias,
+            "42710" => SqlState::DuplicateObject,
+            "42702" => SqlState::AmbiguousColumn,
+            "42725" => SqlState::AmbiguousFunction,
+            "42P08" => SqlState::AmbiguousParameter,
+            "42P09" => SqlState::AmbiguousAlias,
+            "42P10" => SqlState::InvalidColumnReference,
+            "42611" => SqlState::InvalidColumnDefinition,
+            "42P11" => SqlState::InvalidCursorDefinition,
+            "42P12" => SqlState::InvalidDatabaseDefinition,
+            "42P13" => SqlState::InvalidFunctionDefinition,
+            "42P14" => SqlState::InvalidPreparedStatementDefinition,
+            "42P15" => SqlState::InvalidSchemaDefinition,
+            "42P16" => SqlState::InvalidTableDefinition,
+            "42P17" => SqlState::InvalidObjectDefinition,
+            "44000" => SqlState::WithCheckOptionViolation,
+            "53000" => SqlState::InsufficientResources,
+            "53100" => SqlState::DiskFull,
+            "53200" => SqlState::OutOfMemory,
+            "53300" => SqlState::TooManyConnections,
+            "53400" => SqlState::ConfigurationLimitExceeded,
+            "54000" => SqlState::ProgramLimitExceeded,
+            "54001" => SqlState::StatementTooComplex,
+            "54011" => SqlState::TooManyColumns,
+            "54023" => SqlState::TooManyArguments,
+            "55000" => SqlState::ObjectNotInPrerequisiteState,
+            "55006" => SqlState::ObjectInUse,
+            "55P02" => SqlState::CantChangeRuntimeParam,
+            "55P03" => SqlState::LockNotAvailable,
+            "55P04" => SqlState::UnsafeNewEnumValueUsage,
+            "57000" => SqlState::OperatorIntervention,
+            "57014" => SqlState::QueryCanceled,
+            "57P01" => SqlState::AdminShutdown,
+            "57P02" => SqlState::CrashShutdown,
+            "57P03" => SqlState::CannotConnectNow,
+            "57P04" => SqlState::DatabaseDropped,
+            "57P05" => SqlState::IdleSessionTimeout,
+            "58000" => SqlState::SystemError,
+            "58030" => SqlState::IoError,
+            "58P01" => SqlState::UndefinedFile,
+            "58P02" => SqlState::DuplicateFile,
+            "72000" => SqlState::SnapshotTooOld,
+            "F0000" => SqlState::ConfigFileError,
+            "F0001" => SqlState::LockFileExists,
+            "HV000" => SqlState::FdwError,
+            "HV005" => SqlState::FdwColumnNameNotFound,
+            "HV002" => SqlState::FdwDynamicParameterValueNeeded,
+            "HV010" => SqlState::FdwFunctionSequenceError,
+            "HV021" => SqlState::FdwInconsistentDescriptorInformation,
+            "HV024" => SqlState::FdwInvalidAttributeValue,
+            "HV007" => SqlState::FdwInvalidColumnName,
+            "HV008" => SqlState::FdwInvalidColumnNumber,
+            "HV004" => SqlState::FdwInvalidDataType,
+            "HV006" => SqlState::FdwInvalidDataTypeDescriptors,
+            "HV091" => SqlState::FdwInvalidDescriptorFieldIdentifier,
+            "HV00B" => SqlState::FdwInvalidHandle,
+            "HV00C" => SqlState::FdwInvalidOptionIndex,
+            "HV00D" => SqlState::FdwInvalidOptionName,
+            "HV090" => SqlState::FdwInvalidStringLengthOrBufferLength,
+            "HV00A" => SqlState::FdwInvalidStringFormat,
+            "HV009" => SqlState::FdwInvalidUseOfNullPointer,
+            "HV014" => SqlState::FdwTooManyHandles,
+            "HV001" => SqlState::FdwOutOfMemory,
+            "HV00P" => SqlState::FdwNoSchemas,
+            "HV00J" => SqlState::FdwOptionNameNotFound,
+            "HV00K" => SqlState::FdwReplyHandle,
+            "HV00Q" => SqlState::FdwSchemaNotFound,
+            "HV00R" => SqlState::FdwTableNotFound,
+            "HV00L" => SqlState::FdwUnableToCreateExecution,
+            "HV00M" => SqlState::FdwUnableToCreateReply,
+            "HV00N" => SqlState::FdwUnableToEstablishConnection,
+            "P0000" => SqlState::PlpgsqlError,
+            "P0001" => SqlState::RaiseException,
+            "P0002" => SqlState::NoDataFound,
+            "P0003" => SqlState::TooManyRows,
+            "P0004" => SqlState::AssertFailure,
+            "XX000" => SqlState::InternalError,
+            "XX001" => SqlState::DataCorrupted,
+            "XX002" => SqlState::IndexCorrupted,
+            _ => SqlState::Other(code.to_string()),
+        }
+    }
+
+    /// Returns the five-character SQLSTATE code.
+    pub fn code(&self) -> &str {
+        match self {
+            SqlState::SuccessfulCompletion => "00000",
+            SqlState::Warning => "01000",
+            SqlState::DynamicResultSetsReturned => "0100C",
+            SqlState::ImplicitZeroBitPadding => "01008",
+            SqlState::NullValueEliminatedInSetFunction => "01003",
+            SqlState::PrivilegeNotGranted => "01007",
+            SqlState::PrivilegeNotRevoked => "01006",
+            SqlState::WarningStringDataRightTruncation => "01004",
+            SqlState::DeprecatedFeature => "01P01",
+            SqlState::NoData => "02000",
+            SqlState::NoAdditionalDynamicResultSetsReturned => "02001",
+            SqlState::SqlStatementNotYetComplete => "03000",
+            SqlState::ConnectionException => "08000",
+            SqlState::ConnectionDoesNotExist => "08003",
+            SqlState::ConnectionFailure => "08006",
+            SqlState::SqlclientUnableToEstablishSqlconnection => "08001",
+            SqlState::SqlserverRejectedEstablishmentOfSqlconnection => "08004",
+            SqlState::TransactionResolutionUnknown => "08007",
+            SqlState::ProtocolViolation => "08P01",
+            SqlState::TriggeredActionException => "09000",
+            SqlState::FeatureNotSupported => "0A000",
+            SqlState::InvalidTransactionInitiation => "0B000",
+            SqlState::LocatorException => "0F000",
+            SqlState::InvalidLocatorSpecification => "0F001",
+            SqlState::InvalidGrantor => "0L000",
+            SqlState::InvalidGrantOperation => "0LP01",
+            SqlState::InvalidRoleSpecification => "0P000",
+            SqlState::DiagnosticsException => "0Z000",
+            SqlState::StackedDiagnosticsAccessedWithoutActiveHandler => "0Z002",
+            SqlState::CaseNotFound => "20000",
+            SqlState::CardinalityViolation => "21000",
+            SqlState::DataException => "22000",
+            SqlState::ArraySubscriptError => "2202E",
+            SqlState::CharacterNotInRepertoire => "22021",
+            SqlState::DatetimeFieldOverflow => "22008",
+            SqlState::DivisionByZero => "22012",
+            SqlState::ErrorInAssignment => "22005",
+            SqlState::EscapeCharacterConflict => "2200B",
+            SqlState::IndicatorOverflow => "22022",
+            SqlState::IntervalFieldOverflow => "22015",
+            SqlState::InvalidArgumentForLogarithm => "2201E",
+            SqlState::InvalidArgumentForNtileFunction => "22014",
+            SqlState::InvalidArgumentForNthValueFunction => "22016",
+            SqlState::InvalidArgumentForPowerFunction => "2201F",
+            SqlState::InvalidArgumentForWidthBucketFunction => "2201G",
+            SqlState::InvalidCharacterValueForCast => "22018",
+            SqlState::InvalidDatetimeFormat => "22007",
+            SqlState::InvalidEscapeCharacter => "22019",
+            SqlState::InvalidEscapeOctet => "2200D",
+            SqlState::InvalidEscapeSequence => "22025",
+            SqlState::NonstandardUseOfEscapeCharacter => "22P06",
+            SqlState::InvalidIndicatorParameterValue => "22010",
+            SqlState::InvalidParameterValue => "22023",
+            SqlState::InvalidPrecedingOrFollowingSize => "22013",
+            SqlState::InvalidRegularExpression => "2201B",
+            SqlState::InvalidRowCountInLimitClause => "2201W",
+            SqlState::InvalidRowCountInResultOffsetClause => "2201X",
+            SqlState::InvalidTablesampleArgument => "2202H",
+            SqlState::InvalidTablesampleRepeat => "2202G",
+            SqlState::InvalidTimeZoneDisplacementValue => "22009",
+            SqlState::InvalidUseOfEscapeCharacter => "2200C",
+            SqlState::MostSpecificTypeMismatch => "2200G",
+            SqlState::NullValueNotAllowed => "22004",
+            SqlState::NullValueNoIndicatorParameter => "22002",
+            SqlState::NumericValueOutOfRange => "22003",
+            SqlState::SequenceGeneratorLimitExceeded => "2200H",
+            SqlState::StringDataLengthMismatch => "22026",
+            SqlState::StringDataRightTruncation => "22001",
+            SqlState::SubstringError => "22011",
+            SqlState::TrimError => "22027",
+            SqlState::UnterminatedCString => "22024",
+            SqlState::ZeroLengthCharacterString => "2200F",
+            SqlState::FloatingPointException => "22P01",
+            SqlState::InvalidTextRepresentation => "22P02",
+            SqlState::InvalidBinaryRepresentation => "22P03",
+            SqlState::BadCopyFileFormat => "22P04",
+            SqlState::UntranslatableCharacter => "22P05",
+            SqlState::NotAnXmlDocument => "2200L",
+            SqlState::InvalidXmlDocument => "2200M",
+            SqlState::InvalidXmlContent => "2200N",
+            SqlState::InvalidXmlComment => "2200S",
+            SqlState::InvalidXmlProcessingInstruction => "2200T",
+            SqlState::DuplicateJsonObjectKeyValue => "22030",
+            SqlState::InvalidArgumentForSqlJsonDatetimeFunction => "22031",
+            SqlState::InvalidJsonText => "22032",
+            SqlState::InvalidSqlJsonSubscript => "22033",
+            SqlState::MoreThanOneSqlJsonItem => "22034",
+            SqlState::NoSqlJsonItem => "22035",
+            SqlState::NonNumericSqlJsonItem => "22036",
+            SqlState::NonUniqueKeysInAJsonObject => "22037",
+            SqlState::SingletonSqlJsonItemRequired => "22038",
+            SqlState::SqlJsonArrayNotFound => "22039",
+            SqlState::SqlJsonMemberNotFound => "2203A",
+            SqlState::SqlJsonNumberNotFound => "2203B",
+            SqlState::SqlJsonObjectNotFound => "2203C",
+            SqlState::TooManyJsonArrayElements => "2203D",
+            SqlState::TooManyJsonObjectMembers => "2203E",
+            SqlState::SqlJsonScalarRequired => "2203F",
+            SqlState::SqlJsonItemCannotBeCastToTargetType => "2203G",
+            SqlState::IntegrityConstraintViolation => "23000",
+            SqlState::RestrictViolation => "23001",
+            SqlState::NotNullViolation => "23502",
+            SqlState::ForeignKeyViolation => "23503",
+            SqlState::UniqueViolation => "23505",
+            SqlState::CheckViolation => "23514",
+            SqlState::ExclusionViolation => "23P01",
+            SqlState::InvalidCursorState => "24000",
+            SqlState::InvalidTransactionState => "25000",
+            SqlState::ActiveSqlTransaction => "25001",
+            SqlState::BranchTransactionAlreadyActive => "25002",
+            SqlState::HeldCursorRequiresSameIsolationLevel => "25008",
+            SqlState::InappropriateAccessModeForBranchTransaction => "25003",
+            SqlState::InappropriateIsolationLevelForBranchTransaction => "25004",
+            SqlState::NoActiveSqlTransactionForBranchTransaction => "25005",
+            SqlState::ReadOnlySqlTransaction => "25006",
+            SqlState::SchemaAndDataStatementMixingNotSupported => "25007",
+            SqlState::NoActiveSqlTransaction => "25P01",
+            SqlState::InFailedSqlTransaction => "25P02",
+            SqlState::IdleInTransactionSessionTimeout => "25P03",
+            SqlState::InvalidSqlStatementName => "26000",
+            SqlState::TriggeredDataChangeViolation => "27000",
+            SqlState::InvalidAuthorizationSpecification => "28000",
+            SqlState::InvalidPassword => "28P01",
+            SqlState::DependentPrivilegeDescriptorsStillExist => "2B000",
+            SqlState::DependentObjectsStillExist => "2BP01",
+            SqlState::InvalidTransactionTermination => "2D000",
+            SqlState::SqlRoutineException => "2F000",
+            SqlState::FunctionExecutedNoReturnStatement => "2F005",
+            SqlState::SREModifyingSqlDataNotPermitted => "2F002",
+            SqlState::SREProhibitedSqlStatementAttempted => "2F003",
+            SqlState::SREReadingSqlDataNotPermitted => "2F004",
+            SqlState::InvalidCursorName => "34000",
+            SqlState::ExternalRoutineException => "38000",
+            SqlState::ContainingSqlNotPermitted => "38001",
+            SqlState::EREModifyingSqlDataNotPermitted => "38002",
+            SqlState::EREProhibitedSqlStatementAttempted => "38003",
+            SqlState::EREReadingSqlDataNotPermitted => "38004",
+            SqlState::ExternalRoutineInvocationException => "39000",
+            SqlState::InvalidSqlstateReturned => "39001",
+            SqlState::ERIENullValueNotAllowed => "39004",
+            SqlState::TriggerProtocolViolated => "39P01",
+            SqlState::SrfProtocolViolated => "39P02",
+            SqlState::EventTriggerProtocolViolated => "39P03",
+            SqlState::SavepointException => "3B000",
+            SqlState::InvalidSavepointSpecification => "3B001",
+            SqlState::InvalidCatalogName => "3D000",
+            SqlState::InvalidSchemaName => "3F000",
+            SqlState::TransactionRollback => "40000",
+            SqlState::TransactionIntegrityConstraintViolation => "40002",
+            SqlState::SerializationFailure => "40001",
+            SqlState::StatementCompletionUnknown => "40003",
+            SqlState::DeadlockDetected => "40P01",
+            SqlState::SyntaxErrorOrAccessRuleViolation => "42000",
+            SqlState::SyntaxError => "42601",
+            SqlState::InsufficientPrivilege => "42501",
+            SqlState::CannotCoerce => "42846",
+            SqlState::GroupingError => "42803",
+            SqlState::WindowingError => "42P20",
+            SqlState::InvalidRecursion => "42P19",
+            SqlState::InvalidForeignKey => "42830",
+            SqlState::InvalidName => "42602",
+            SqlState::NameTooLong => "42622",
+            SqlState::ReservedName => "42939",
+            SqlState::DatatypeMismatch => "42804",
+            SqlState::IndeterminateDatatype => "42P18",
+            SqlState::CollationMismatch => "42P21",
+            SqlState::IndeterminateCollation => "42P22",
+            SqlState::WrongObjectType => "42809",
+            SqlState::GeneratedAlways => "428C9",
+            SqlState::UndefinedColumn => "42703",
+            SqlState::UndefinedFunction => "42883",
+            SqlState::UndefinedTable => "42P01",
+            SqlState::UndefinedParameter => "42P02",
+            SqlState::UndefinedObject => "42704",
+            SqlState::DuplicateColumn => "42701",
+            SqlState::DuplicateCursor => "42P03",
+            SqlState::DuplicateDatabase => "42P04",
+            SqlState::DuplicateFunction => "42723",
+            SqlState::DuplicatePreparedStatement => "42P05",
+            SqlState::DuplicateSchema => "42P06",
+            SqlState::DuplicateTable => "42P07",
+            SqlState::DuplicateAlias => "42712",
+            SqlState::DuplicateObject => "42710",
+            SqlState::AmbiguousColumn => "42702",
+            SqlState::AmbiguousFunction => "42725",
+            SqlState::AmbiguousParameter => "42P08",
+            SqlState::AmbiguousAlias => "42P09",
+            SqlState::InvalidColumnReference => "42P10",
+            SqlState::InvalidColumnDefinition => "42611",
+            SqlState::InvalidCursorDefinition => "42P11",
+            SqlState::InvalidDatabaseDefinition => "42P12",
+            SqlState::InvalidFunctionDefinition => "42P13",
+            SqlState::InvalidPreparedStatementDefinition => "42P14",
+            SqlState::InvalidSchemaDefinition => "42P15",
+            SqlState::InvalidTableDefinition => "42P16",
+            SqlState::InvalidObjectDefinition => "42P17",
+            SqlState::WithCheckOptionViolation => "44000",
+            SqlState::InsufficientResources => "53000",
+            SqlState::DiskFull => "53100",
+            SqlState::OutOfMemory => "53200",
+            SqlState::TooManyConnections => "53300",
+            SqlState::ConfigurationLimitExceeded => "53400",
+            SqlState::ProgramLimitExceeded => "54000",
+            SqlState::StatementTooComplex => "54001",
+            SqlState::TooManyColumns => "54011",
+            SqlState::TooManyArguments => "54023",
+            SqlState::ObjectNotInPrerequisiteState => "55000",
+            SqlState::ObjectInUse => "55006",
+            SqlState::CantChangeRuntimeParam => "55P02",
+            SqlState::LockNotAvailable => "55P03",
+            SqlState::UnsafeNewEnumValueUsage => "55P04",
+            SqlState::OperatorIntervention => "57000",
+            SqlState::QueryCanceled => "57014",
+            SqlState::AdminShutdown => "57P01",
+            SqlState::CrashShutdown => "57P02",
+            SqlState::CannotConnectNow => "57P03",
+            SqlState::DatabaseDropped => "57P04",
+            SqlState::IdleSessionTimeout => "57P05",
+            SqlState::SystemError => "58000",
+            SqlState::IoError => "58030",
+            SqlState::UndefinedFile => "58P01",
+            SqlState::DuplicateFile => "58P02",
+            SqlState::SnapshotTooOld => "72000",
+            SqlState::ConfigFileError => "F0000",
+            SqlState::LockFileExists => "F0001",
+            SqlState::FdwError => "HV000",
+            SqlState::FdwColumnNameNotFound => "HV005",
+            SqlState::FdwDynamicParameterValueNeeded => "HV002",
+            SqlState::FdwFunctionSequenceError => "HV010",
+            SqlState::FdwInconsistentDescriptorInformation => "HV021",
+            SqlState::FdwInvalidAttributeValue => "HV024",
+            SqlState::FdwInvalidColumnName => "HV007",
+            SqlState::FdwInvalidColumnNumber => "HV008",
+            SqlState::FdwInvalidDataType => "HV004",
+            SqlState::FdwInvalidDataTypeDescriptors => "HV006",
+            SqlState::FdwInvalidDescriptorFieldIdentifier => "HV091",
+            SqlState::FdwInvalidHandle => "HV00B",
+            SqlState::FdwInvalidOptionIndex => "HV00C",
+            SqlState::FdwInvalidOptionName => "HV00D",
+            SqlState::FdwInvalidStringLengthOrBufferLength => "HV090",
+            SqlState::FdwInvalidStringFormat => "HV00A",
+            SqlState::FdwInvalidUseOfNullPointer => "HV009",
+            SqlState::FdwTooManyHandles => "HV014",
+            SqlState::FdwOutOfMemory => "HV001",
+            SqlState::FdwNoSchemas => "HV00P",
+            SqlState::FdwOptionNameNotFound => "HV00J",
+            SqlState::FdwReplyHandle => "HV00K",
+            SqlState::FdwSchemaNotFound => "HV00Q",
+            SqlState::FdwTableNotFound => "HV00R",
+            SqlState::FdwUnableToCreateExecution => "HV00L",
+            SqlState::FdwUnableToCreateReply => "HV00M",
+            SqlState::FdwUnableToEstablishConnection => "HV00N",
+            SqlState::PlpgsqlError => "P0000",
+            SqlState::RaiseException => "P0001",
+            SqlState::NoDataFound => "P0002",
+            SqlState::TooManyRows => "P0003",
+            SqlState::AssertFailure => "P0004",
+            SqlState::InternalError => "XX000",
+            SqlState::DataCorrupted => "XX001",
+            SqlState::IndexCorrupted => "XX002",
+            SqlState::Other(code) => code,
+        }
+    }
+}
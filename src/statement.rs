@@ -0,0 +1,147 @@
+use crate::client::Column;
+use postgres_types::Type;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::sync::Arc;
+
+/// Names of statements whose handles have been dropped, waiting for the
+/// connection to send `Close` for them.
+pub(crate) type CloseQueue = Rc<RefCell<Vec<String>>>;
+
+/// A prepared statement, created by [`Client::prepare`](crate::Client::prepare).
+///
+/// It can be passed to `query`, `execute` and the other query methods in place
+/// of SQL text, skipping the lookup in the connection's statement cache.
+/// Clones share the same server-side statement, which is closed once the last
+/// of them is dropped. A statement only exists on the connection that
+/// prepared it.
+#[derive(Clone)]
+pub struct Statement(Rc<StatementInner>);
+
+struct StatementInner {
+    /// Empty for the unnamed statement, which is parsed again each time it
+    /// is executed.
+    name: String,
+    query: String,
+    params: Vec<Type>,
+    columns: Arc<Vec<Column>>,
+    close_queue: Weak<RefCell<Vec<String>>>,
+}
+
+impl Statement {
+    pub(crate) fn new(
+        name: String,
+        query: &str,
+        params: Vec<Type>,
+        columns: Vec<Column>,
+        close_queue: &CloseQueue,
+    ) -> Self {
+        Self(Rc::new(StatementInner {
+            name,
+            query: query.to_string(),
+            params,
+            columns: Arc::new(columns),
+            close_queue: Rc::downgrade(close_queue),
+        }))
+    }
+
+    /// A description of `query` as the unnamed statement, which needs no
+    /// closing.
+    pub(crate) fn unnamed(query: &str, params: Vec<Type>, columns: Vec<Column>) -> Self {
+        Self(Rc::new(StatementInner {
+            name: String::new(),
+            query: query.to_string(),
+            params,
+            columns: Arc::new(columns),
+            close_queue: Weak::new(),
+        }))
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    pub(crate) fn query(&self) -> &str {
+        &self.0.query
+    }
+
+    /// Returns the types of the statement's parameters.
+    pub fn params(&self) -> &[Type] {
+        &self.0.params
+    }
+
+    /// Returns the columns of the rows the statement returns, which is empty
+    /// for statements that return no rows.
+    pub fn columns(&self) -> &[Column] {
+        &self.0.columns
+    }
+
+    pub(crate) fn column_list(&self) -> &Arc<Vec<Column>> {
+        &self.0.columns
+    }
+
+    /// Whether the statement was prepared on the connection owning `queue`.
+    pub(crate) fn belongs_to(&self, queue: &CloseQueue) -> bool {
+        self.0.close_queue.as_ptr() == Rc::as_ptr(queue)
+    }
+}
+
+impl PartialEq for Statement {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Drop for StatementInner {
+    fn drop(&mut self) {
+        // Nothing to do if the connection is already gone.
+        if let Some(queue) = self.close_queue.upgrade() {
+            queue.borrow_mut().push(std::mem::take(&mut self.name));
+        }
+    }
+}
+
+/// Either SQL text or a prepared [`Statement`].
+///
+/// This trait is sealed and implemented for `str`, `String` and `Statement`.
+pub trait ToStatement: sealed::Sealed {}
+
+pub(crate) mod sealed {
+    use super::Statement;
+
+    pub enum StatementRef<'a> {
+        Query(&'a str),
+        Statement(&'a Statement),
+    }
+
+    pub trait Sealed {
+        #[doc(hidden)]
+        fn statement_ref(&self) -> StatementRef<'_>;
+    }
+}
+
+use sealed::StatementRef;
+
+impl ToStatement for str {}
+
+impl sealed::Sealed for str {
+    fn statement_ref(&self) -> StatementRef<'_> {
+        StatementRef::Query(self)
+    }
+}
+
+impl ToStatement for String {}
+
+impl sealed::Sealed for String {
+    fn statement_ref(&self) -> StatementRef<'_> {
+        StatementRef::Query(self)
+    }
+}
+
+impl ToStatement for Statement {}
+
+impl sealed::Sealed for Statement {
+    fn statement_ref(&self) -> StatementRef<'_> {
+        StatementRef::Statement(self)
+    }
+}
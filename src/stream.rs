@@ -0,0 +1,133 @@
+use crate::error::{Error, Result};
+use crate::tls::{self, TlsConfig};
+use monoio::BufResult;
+use monoio::buf::{IoBuf, IoBufMut, IoVecBuf, IoVecBufMut};
+use monoio::io::{AsyncReadRent, AsyncWriteRent};
+use monoio::net::TcpStream;
+#[cfg(unix)]
+use monoio::net::UnixStream;
+use std::time::Duration;
+
+/// TCP keepalive settings, each left to the operating system if `None`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Keepalive {
+    pub(crate) idle: Option<Duration>,
+    pub(crate) interval: Option<Duration>,
+    pub(crate) retries: Option<u32>,
+}
+
+/// The socket a [`Connection`](crate::connection::Connection) talks over.
+pub enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<monoio_rustls::ClientTlsStream<TcpStream>>),
+}
+
+impl Stream {
+    /// Waits until the socket has data to read without reading any, so the
+    /// wait can be dropped safely. Returns `None` straight away for TLS,
+    /// where data may already be buffered above the socket.
+    pub(crate) async fn readable(&self) -> Option<std::io::Result<()>> {
+        match self {
+            Stream::Tcp(s) => Some(s.readable(false).await),
+            #[cfg(unix)]
+            Stream::Unix(s) => Some(s.readable(false).await),
+            #[cfg(feature = "tls")]
+            Stream::Tls(_) => None,
+        }
+    }
+}
+
+impl AsyncReadRent for Stream {
+    async fn read<T: IoBufMut>(&mut self, buf: T) -> BufResult<usize, T> {
+        match self {
+            Stream::Tcp(s) => s.read(buf).await,
+            #[cfg(unix)]
+            Stream::Unix(s) => s.read(buf).await,
+            #[cfg(feature = "tls")]
+            Stream::Tls(s) => s.read(buf).await,
+        }
+    }
+
+    async fn readv<T: IoVecBufMut>(&mut self, buf: T) -> BufResult<usize, T> {
+        match self {
+            Stream::Tcp(s) => s.readv(buf).await,
+            #[cfg(unix)]
+            Stream::Unix(s) => s.readv(buf).await,
+            #[cfg(feature = "tls")]
+            Stream::Tls(s) => s.readv(buf).await,
+        }
+    }
+}
+
+impl AsyncWriteRent for Stream {
+    async fn write<T: IoBuf>(&mut self, buf: T) -> BufResult<usize, T> {
+        match self {
+            Stream::Tcp(s) => s.write(buf).await,
+            #[cfg(unix)]
+            Stream::Unix(s) => s.write(buf).await,
+            #[cfg(feature = "tls")]
+            Stream::Tls(s) => s.write(buf).await,
+        }
+    }
+
+    async fn writev<T: IoVecBuf>(&mut self, buf_vec: T) -> BufResult<usize, T> {
+        match self {
+            Stream::Tcp(s) => s.writev(buf_vec).await,
+            #[cfg(unix)]
+            Stream::Unix(s) => s.writev(buf_vec).await,
+            #[cfg(feature = "tls")]
+            Stream::Tls(s) => s.writev(buf_vec).await,
+        }
+    }
+
+    async fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(s) => s.flush().await,
+            #[cfg(unix)]
+            Stream::Unix(s) => s.flush().await,
+            #[cfg(feature = "tls")]
+            Stream::Tls(s) => s.flush().await,
+        }
+    }
+
+    async fn shutdown(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(s) => s.shutdown().await,
+            #[cfg(unix)]
+            Stream::Unix(s) => s.shutdown().await,
+            #[cfg(feature = "tls")]
+            Stream::Tls(s) => s.shutdown().await,
+        }
+    }
+}
+
+/// Opens a connection to the server at `host` and `port`, negotiating TLS
+/// according to `tls`. As with libpq, a `host` starting with `/` is the
+/// directory holding the server's Unix socket, over which TLS is not used and
+/// `keepalive` does not apply.
+pub(crate) async fn connect(
+    host: &str,
+    port: u16,
+    tls: &TlsConfig,
+    keepalive: Option<Keepalive>,
+) -> Result<Stream> {
+    #[cfg(unix)]
+    if host.starts_with('/') {
+        let path = std::path::Path::new(host).join(format!(".s.PGSQL.{}", port));
+        return Ok(Stream::Unix(UnixStream::connect(path).await?));
+    }
+
+    let stream = TcpStream::connect((host, port)).await?;
+    stream
+        .set_nodelay(true)
+        .map_err(|e| Error::Other(e.to_string()))?;
+    if let Some(keepalive) = keepalive {
+        stream
+            .set_tcp_keepalive(keepalive.idle, keepalive.interval, keepalive.retries)
+            .map_err(|e| Error::Other(e.to_string()))?;
+    }
+    tls::connect(stream, host, tls).await
+}
@@ -0,0 +1,304 @@
+use crate::error::{Error, Result};
+use crate::stream::Stream;
+use monoio::net::TcpStream;
+use std::str::FromStr;
+
+/// How hard to insist on TLS, using the same names and semantics as libpq's
+/// `sslmode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SslMode {
+    /// Never send an `SSLRequest`.
+    #[default]
+    Disable,
+    /// Use TLS if the server supports it, otherwise fall back to plain TCP.
+    /// The certificate is not verified.
+    Prefer,
+    /// Fail unless the server supports TLS. The certificate is not verified
+    /// unless root certificates are configured, in which case this behaves
+    /// like [`SslMode::VerifyCa`].
+    Require,
+    /// Require TLS and check that the certificate chains to a configured root.
+    VerifyCa,
+    /// Like [`SslMode::VerifyCa`], and also check that the certificate is
+    /// valid for the host name being connected to.
+    VerifyFull,
+}
+
+impl FromStr for SslMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "disable" => Ok(SslMode::Disable),
+            "prefer" => Ok(SslMode::Prefer),
+            "require" => Ok(SslMode::Require),
+            "verify-ca" => Ok(SslMode::VerifyCa),
+            "verify-full" => Ok(SslMode::VerifyFull),
+            _ => Err(Error::Config(format!("invalid sslmode: {}", s))),
+        }
+    }
+}
+
+/// TLS settings for a connection.
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    mode: SslMode,
+    #[cfg(feature = "tls")]
+    roots: Vec<rustls::pki_types::CertificateDer<'static>>,
+}
+
+impl TlsConfig {
+    pub fn new(mode: SslMode) -> Self {
+        Self {
+            mode,
+            #[cfg(feature = "tls")]
+            roots: Vec::new(),
+        }
+    }
+
+    pub fn mode(&self) -> SslMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: SslMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Trusts a DER-encoded root certificate.
+    #[cfg(feature = "tls")]
+    pub fn add_root_certificate(
+        &mut self,
+        cert: rustls::pki_types::CertificateDer<'static>,
+    ) -> &mut Self {
+        self.roots.push(cert);
+        self
+    }
+
+    /// Trusts every certificate in a PEM bundle, such as the contents of a
+    /// libpq `sslrootcert` file.
+    #[cfg(feature = "tls")]
+    pub fn add_root_certificates_pem(&mut self, pem: &[u8]) -> Result<&mut Self> {
+        use rustls::pki_types::CertificateDer;
+        use rustls::pki_types::pem::PemObject;
+
+        for cert in CertificateDer::pem_slice_iter(pem) {
+            let cert = cert.map_err(|e| Error::Tls(e.to_string()))?;
+            self.roots.push(cert);
+        }
+        Ok(self)
+    }
+}
+
+/// Negotiates TLS on a freshly opened socket according to `config`. `host` is
+/// the name the certificate is checked against in `verify-full` mode.
+pub(crate) async fn connect(stream: TcpStream, host: &str, config: &TlsConfig) -> Result<Stream> {
+    match config.mode {
+        SslMode::Disable => Ok(Stream::Tcp(stream)),
+        #[cfg(not(feature = "tls"))]
+        SslMode::Prefer => Ok(Stream::Tcp(stream)),
+        #[cfg(not(feature = "tls"))]
+        _ => {
+            let _ = host;
+            Err(Error::Tls(
+                "TLS support requires the `tls` cargo feature".into(),
+            ))
+        }
+        #[cfg(feature = "tls")]
+        _ => imp::connect(stream, host, config).await,
+    }
+}
+
+#[cfg(feature = "tls")]
+mod imp {
+    use super::{SslMode, TlsConfig};
+    use crate::error::{Error, Result};
+    use crate::stream::Stream;
+    use bytes::BytesMut;
+    use monoio::io::{AsyncReadRentExt, AsyncWriteRentExt};
+    use monoio::net::TcpStream;
+    use monoio_rustls::TlsConnector;
+    use postgres_protocol::message::frontend;
+    use rustls::client::WebPkiServerVerifier;
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature};
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::{CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore};
+    use std::sync::Arc;
+
+    pub(super) async fn connect(
+        mut stream: TcpStream,
+        host: &str,
+        config: &TlsConfig,
+    ) -> Result<Stream> {
+        let mut buf = BytesMut::new();
+        frontend::ssl_request(&mut buf);
+        let (res, _) = stream.write_all(buf).await;
+        res?;
+
+        let (res, reply) = stream.read_exact(vec![0u8; 1]).await;
+        res?;
+        match reply[0] {
+            b'S' => {}
+            b'N' if config.mode == SslMode::Prefer => return Ok(Stream::Tcp(stream)),
+            b'N' => return Err(Error::Tls("server does not support TLS".into())),
+            other => {
+                return Err(Error::Protocol(format!(
+                    "unexpected response to SSLRequest: {:?}",
+                    other as char
+                )));
+            }
+        }
+
+        let server_name = ServerName::try_from(host.to_string())
+            .map_err(|e| Error::Tls(format!("invalid server name {}: {}", host, e)))?;
+        let connector = TlsConnector::from(client_config(config)?);
+        let tls = connector
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| Error::Tls(e.to_string()))?;
+        Ok(Stream::Tls(Box::new(tls)))
+    }
+
+    fn client_config(config: &TlsConfig) -> Result<ClientConfig> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verify_chain = match config.mode {
+            SslMode::VerifyCa | SslMode::VerifyFull => true,
+            SslMode::Require => !config.roots.is_empty(),
+            SslMode::Disable | SslMode::Prefer => false,
+        };
+
+        let verifier: Arc<dyn ServerCertVerifier> = if verify_chain {
+            if config.roots.is_empty() {
+                return Err(Error::Tls(
+                    "certificate verification requires at least one root certificate".into(),
+                ));
+            }
+            let mut store = RootCertStore::empty();
+            for cert in &config.roots {
+                store
+                    .add(cert.clone())
+                    .map_err(|e| Error::Tls(e.to_string()))?;
+            }
+            let webpki =
+                WebPkiServerVerifier::builder_with_provider(Arc::new(store), provider.clone())
+                    .build()
+                    .map_err(|e| Error::Tls(e.to_string()))?;
+            if config.mode == SslMode::VerifyFull {
+                webpki
+            } else {
+                Arc::new(SkipHostname(webpki))
+            }
+        } else {
+            Arc::new(NoVerification(provider.clone()))
+        };
+
+        Ok(ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| Error::Tls(e.to_string()))?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier)
+            .with_no_client_auth())
+    }
+
+    /// `verify-ca`: the chain must be trusted but the host name is ignored.
+    #[derive(Debug)]
+    struct SkipHostname(Arc<WebPkiServerVerifier>);
+
+    impl ServerCertVerifier for SkipHostname {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            intermediates: &[CertificateDer<'_>],
+            server_name: &ServerName<'_>,
+            ocsp_response: &[u8],
+            now: UnixTime,
+        ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+            match self.0.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            ) {
+                Err(rustls::Error::InvalidCertificate(
+                    CertificateError::NotValidForName
+                    | CertificateError::NotValidForNameContext { .. },
+                )) => Ok(ServerCertVerified::assertion()),
+                res => res,
+            }
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+            self.0.verify_tls12_signature(message, cert, dss)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+            self.0.verify_tls13_signature(message, cert, dss)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+            self.0.supported_verify_schemes()
+        }
+    }
+
+    /// `prefer`/`require`: encrypt, but accept any certificate. Handshake
+    /// signatures are still checked so the session keys are bound to it.
+    #[derive(Debug)]
+    struct NoVerification(Arc<CryptoProvider>);
+
+    impl ServerCertVerifier for NoVerification {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+            verify_tls12_signature(
+                message,
+                cert,
+                dss,
+                &self.0.signature_verification_algorithms,
+            )
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+            verify_tls13_signature(
+                message,
+                cert,
+                dss,
+                &self.0.signature_verification_algorithms,
+            )
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+            self.0.signature_verification_algorithms.supported_schemes()
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::client::{self, CommandTag, Row};
+use crate::connection::Connection;
+use crate::copy::{CopyInSink, CopyOutStream};
+use crate::error::Result;
+use crate::pipeline::Pipeline;
+use crate::row_stream::RowStream;
+use crate::simple_query::SimpleQueryResult;
+use crate::statement::{Statement, ToStatement};
+use postgres_types::{ToSql, Type};
+
+/// The isolation level of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsolationLevel {
+    /// Treated as `ReadCommitted` by PostgreSQL.
+    ReadUncommitted,
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    fn as_sql(self) -> &'static str {
+        match self {
+            IsolationLevel::ReadUncommitted => "READ UNCOMMITTED",
+            IsolationLevel::ReadCommitted => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+/// A transaction, or a savepoint inside one.
+///
+/// A transaction that is dropped without calling [`Transaction::commit`] is
+/// rolled back. Since that cannot be awaited in `Drop`, the rollback is queued
+/// on the connection and sent before whatever uses it next, so a client
+/// returned to a [`Pool`](crate::Pool) is never handed out mid-transaction.
+pub struct Transaction<'a> {
+    connection: &'a mut Connection,
+    depth: u32,
+    done: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) async fn begin(connection: &'a mut Connection, sql: &str) -> Result<Self> {
+        connection.batch_execute(sql).await?;
+        Ok(Self {
+            connection,
+            depth: 0,
+            done: false,
+        })
+    }
+
+    /// Commits the transaction, or releases the savepoint.
+    pub async fn commit(mut self) -> Result<()> {
+        self.done = true;
+        let sql = if self.depth == 0 {
+            "COMMIT".to_string()
+        } else {
+            format!("RELEASE SAVEPOINT {}", self.savepoint())
+        };
+        self.connection.batch_execute(&sql).await
+    }
+
+    /// Rolls back the transaction, or everything done since the savepoint.
+    pub async fn rollback(mut self) -> Result<()> {
+        self.done = true;
+        let sql = self.rollback_sql();
+        self.connection.batch_execute(&sql).await
+    }
+
+    /// Starts a nested transaction backed by a savepoint.
+    pub async fn transaction(&mut self) -> Result<Transaction<'_>> {
+        let depth = self.depth + 1;
+        self.connection
+            .batch_execute(&format!("SAVEPOINT sp_{}", depth))
+            .await?;
+        Ok(Transaction {
+            connection: self.connection,
+            depth,
+            done: false,
+        })
+    }
+
+    /// See [`Client::prepare`](crate::Client::prepare).
+    pub async fn prepare(&mut self, query: &str) -> Result<Statement> {
+        self.connection.prepare(query).await
+    }
+
+    /// See [`Client::prepare_typed`](crate::Client::prepare_typed).
+    pub async fn prepare_typed(&mut self, query: &str, types: &[Type]) -> Result<Statement> {
+        self.connection.prepare_typed(query, types).await
+    }
+
+    /// Executes a statement and returns the number of rows it affected.
+    pub async fn execute<T>(&mut self, statement: &T, params: &[&(dyn ToSql + Sync)]) -> Result<u64>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.connection.execute(statement, params).await
+    }
+
+    pub async fn query<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.connection.query(statement, params).await
+    }
+
+    /// Like [`Transaction::query`], but also returns the command tag.
+    pub async fn query_with_tag<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<(Vec<Row>, CommandTag)>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.connection.query_with_tag(statement, params).await
+    }
+
+    /// See [`Client::query_stream`](crate::Client::query_stream).
+    pub async fn query_stream<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<RowStream<'_>>
+    where
+        T: ?Sized + ToStatement,
+    {
+        RowStream::start(self.connection, statement, params, 0).await
+    }
+
+    /// See [`Client::query_stream_bounded`](crate::Client::query_stream_bounded).
+    pub async fn query_stream_bounded<T>(
+        &mut self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+        max_rows: i32,
+    ) -> Result<RowStream<'_>>
+    where
+        T: ?Sized + ToStatement,
+    {
+        client::check_max_rows(max_rows)?;
+        RowStream::start(self.connection, statement, params, max_rows).await
+    }
+
+    pub async fn batch_execute(&mut self, query: &str) -> Result<()> {
+        self.connection.batch_execute(query).await
+    }
+
+    /// See [`Client::simple_query`](crate::Client::simple_query).
+    pub async fn simple_query(&mut self, query: &str) -> Result<Vec<SimpleQueryResult>> {
+        self.connection.simple_query(query).await
+    }
+
+    /// See [`Client::copy_in`](crate::Client::copy_in).
+    pub async fn copy_in(&mut self, query: &str) -> Result<CopyInSink<'_>> {
+        CopyInSink::start(self.connection, query).await
+    }
+
+    /// See [`Client::copy_out`](crate::Client::copy_out).
+    pub async fn copy_out(&mut self, query: &str) -> Result<CopyOutStream<'_>> {
+        CopyOutStream::start(self.connection, query).await
+    }
+
+    /// See [`Client::pipeline`](crate::Client::pipeline).
+    pub fn pipeline(&mut self) -> Pipeline<'_> {
+        Pipeline::new(self.connection)
+    }
+
+    fn savepoint(&self) -> String {
+        format!("sp_{}", self.depth)
+    }
+
+    fn rollback_sql(&self) -> String {
+        if self.depth == 0 {
+            "ROLLBACK".to_string()
+        } else {
+            // Releasing the savepoint as well keeps the names of later
+            // savepoints at this depth unambiguous.
+            let name = self.savepoint();
+            format!("ROLLBACK TO SAVEPOINT {name}; RELEASE SAVEPOINT {name}")
+        }
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            let sql = self.rollback_sql();
+            self.connection.defer(sql);
+        }
+    }
+}
+
+/// Options for starting a transaction, created by
+/// [`Client::build_transaction`](crate::Client::build_transaction).
+pub struct TransactionBuilder<'a> {
+    connection: &'a mut Connection,
+    isolation_level: Option<IsolationLevel>,
+    read_only: Option<bool>,
+    deferrable: Option<bool>,
+}
+
+impl<'a> TransactionBuilder<'a> {
+    pub(crate) fn new(connection: &'a mut Connection) -> Self {
+        Self {
+            connection,
+            isolation_level: None,
+            read_only: None,
+            deferrable: None,
+        }
+    }
+
+    pub fn isolation_level(mut self, level: IsolationLevel) -> Self {
+        self.isolation_level = Some(level);
+        self
+    }
+
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = Some(read_only);
+        self
+    }
+
+    /// Only has an effect on `SERIALIZABLE READ ONLY` transactions, which
+    /// then wait for a safe snapshot instead of risking serialization
+    /// failures.
+    pub fn deferrable(mut self, deferrable: bool) -> Self {
+        self.deferrable = Some(deferrable);
+        self
+    }
+
+    /// Sends `BEGIN` with the chosen options.
+    pub async fn start(self) -> Result<Transaction<'a>> {
+        let mut modes = Vec::new();
+        if let Some(level) = self.isolation_level {
+            modes.push(format!("ISOLATION LEVEL {}", level.as_sql()));
+        }
+        match self.read_only {
+            Some(true) => modes.push("READ ONLY".to_string()),
+            Some(false) => modes.push("READ WRITE".to_string()),
+            None => {}
+        }
+        match self.deferrable {
+            Some(true) => modes.push("DEFERRABLE".to_string()),
+            Some(false) => modes.push("NOT DEFERRABLE".to_string()),
+            None => {}
+        }
+
+        let mut sql = "BEGIN".to_string();
+        if !modes.is_empty() {
+            sql.push(' ');
+            sql.push_str(&modes.join(", "));
+        }
+        Transaction::begin(self.connection, &sql).await
+    }
+}
@@ -0,0 +1,74 @@
+use monoio_pg::{Client, FromRow};
+
+const HOST: &str = "127.0.0.1:5432";
+const USER: &str = "monoio";
+const PASS: &str = "monoio";
+
+async fn get_client() -> Client {
+    Client::connect(HOST, USER, Some(PASS), Some("postgres"))
+        .await
+        .expect("Failed to connect to Postgres")
+}
+
+#[derive(Debug, PartialEq, FromRow)]
+struct User {
+    id: i32,
+    #[from_row(rename = "user_name")]
+    name: String,
+    email: Option<String>,
+}
+
+#[monoio::test_all]
+async fn test_derive_from_row() {
+    let mut client = get_client().await;
+
+    // Column order need not match the field order.
+    let rows = client
+        .query(
+            "SELECT NULL::TEXT AS email, 'alice' AS user_name, 1 AS id \
+             UNION ALL SELECT 'bob@example.com', 'bob', 2",
+            &[],
+        )
+        .await
+        .unwrap();
+    let users = rows
+        .iter()
+        .map(User::from_row)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        users,
+        [
+            User {
+                id: 1,
+                name: "alice".into(),
+                email: None,
+            },
+            User {
+                id: 2,
+                name: "bob".into(),
+                email: Some("bob@example.com".into()),
+            },
+        ]
+    );
+}
+
+#[monoio::test_all]
+async fn test_derive_from_row_errors() {
+    let mut client = get_client().await;
+
+    let rows = client
+        .query("SELECT 1 AS id, 'alice' AS name", &[])
+        .await
+        .unwrap();
+    assert!(User::from_row(&rows[0]).is_err());
+
+    let rows = client
+        .query(
+            "SELECT 1::INT8 AS id, 'alice' AS user_name, NULL::TEXT AS email",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert!(User::from_row(&rows[0]).is_err());
+}
@@ -1,4 +1,10 @@
-use monoio_pg::{Client, Pool};
+use monoio::io::stream::Stream;
+use monoio::io::{AsyncReadRent, AsyncReadRentExt, AsyncWriteRent, AsyncWriteRentExt};
+use monoio_pg::types::{self as postgres_types, FromSql, Kind, ToSql, Type};
+use monoio_pg::{
+    Client, CommandTag, Config, Error, ErrorPosition, IsolationLevel, LoadBalanceHosts, Pool,
+    SqlState, TargetSessionAttrs, TransactionStatus,
+};
 
 const HOST: &str = "127.0.0.1:5432";
 const USER: &str = "monoio";
@@ -10,6 +16,12 @@ async fn get_client() -> Client {
         .expect("Failed to connect to Postgres")
 }
 
+fn config() -> Config {
+    format!("postgres://{}:{}@{}/postgres", USER, PASS, HOST)
+        .parse()
+        .unwrap()
+}
+
 #[monoio::test_all]
 async fn test_connect_success() {
     let _client = get_client().await;
@@ -26,39 +38,44 @@ async fn test_execute_create_drop() {
     let mut client = get_client().await;
 
     // Drop if exists
-    let _ = client.execute("DROP TABLE IF EXISTS test_execute").await;
+    let _ = client
+        .execute("DROP TABLE IF EXISTS test_execute", &[])
+        .await;
 
     // Create
     client
-        .execute("CREATE TABLE test_execute (id INT)")
+        .execute("CREATE TABLE test_execute (id INT)", &[])
         .await
         .unwrap();
 
     // Insert
     client
-        .execute("INSERT INTO test_execute VALUES (1)")
+        .execute("INSERT INTO test_execute VALUES (1)", &[])
         .await
         .unwrap();
     client
-        .execute("INSERT INTO test_execute VALUES (2)")
+        .execute("INSERT INTO test_execute VALUES (2)", &[])
         .await
         .unwrap();
 
     // Drop
-    client.execute("DROP TABLE test_execute").await.unwrap();
+    client
+        .execute("DROP TABLE test_execute", &[])
+        .await
+        .unwrap();
 }
 
 #[monoio::test_all]
 async fn test_query_simple() {
     let mut client = get_client().await;
     let rows = client
-        .query("SELECT 42 as num, 'hello' as str")
+        .query("SELECT 42 as num, 'hello' as str", &[])
         .await
         .unwrap();
     assert_eq!(rows.len(), 1);
 
-    let num: i32 = rows[0].get(0).unwrap();
-    let str_val: String = rows[0].get(1).unwrap();
+    let num: i32 = rows[0].get(0);
+    let str_val: String = rows[0].get(1);
 
     assert_eq!(num, 42);
     assert_eq!(str_val, "hello");
@@ -67,47 +84,50 @@ async fn test_query_simple() {
 #[monoio::test_all]
 async fn test_query_multiple_rows() {
     let mut client = get_client().await;
-    let _ = client.execute("DROP TABLE IF EXISTS test_multi").await;
+    let _ = client.execute("DROP TABLE IF EXISTS test_multi", &[]).await;
     client
-        .execute("CREATE TABLE test_multi (id INT)")
+        .execute("CREATE TABLE test_multi (id INT)", &[])
         .await
         .unwrap();
     client
-        .execute("INSERT INTO test_multi VALUES (1), (2), (3)")
+        .execute("INSERT INTO test_multi VALUES (1), (2), (3)", &[])
         .await
         .unwrap();
 
     let rows = client
-        .query("SELECT id FROM test_multi ORDER BY id")
+        .query("SELECT id FROM test_multi ORDER BY id", &[])
         .await
         .unwrap();
     assert_eq!(rows.len(), 3);
 
-    let id1: i32 = rows[0].get(0).unwrap();
-    let id2: i32 = rows[1].get(0).unwrap();
-    let id3: i32 = rows[2].get(0).unwrap();
+    let id1: i32 = rows[0].get(0);
+    let id2: i32 = rows[1].get(0);
+    let id3: i32 = rows[2].get(0);
 
     assert_eq!(id1, 1);
     assert_eq!(id2, 2);
     assert_eq!(id3, 3);
 
-    client.execute("DROP TABLE test_multi").await.unwrap();
+    client.execute("DROP TABLE test_multi", &[]).await.unwrap();
 }
 
 #[monoio::test_all]
 async fn test_query_null_values() {
     let mut client = get_client().await;
-    let _ = client.execute("DROP TABLE IF EXISTS test_null").await;
+    let _ = client.execute("DROP TABLE IF EXISTS test_null", &[]).await;
     client
-        .execute("CREATE TABLE test_null (id INT, val TEXT)")
+        .execute("CREATE TABLE test_null (id INT, val TEXT)", &[])
         .await
         .unwrap();
     client
-        .execute("INSERT INTO test_null VALUES (1, NULL)")
+        .execute("INSERT INTO test_null VALUES (1, NULL)", &[])
         .await
         .unwrap();
 
-    let rows = client.query("SELECT val FROM test_null").await.unwrap();
+    let rows = client
+        .query("SELECT val FROM test_null", &[])
+        .await
+        .unwrap();
     assert_eq!(rows.len(), 1);
 
     // In our simplified FromSql, we might error on NULL if we try to get a String instead of Option<String>.
@@ -116,18 +136,18 @@ async fn test_query_null_values() {
     let raw = rows[0].get_raw(0);
     assert!(raw.is_none());
 
-    client.execute("DROP TABLE test_null").await.unwrap();
+    client.execute("DROP TABLE test_null", &[]).await.unwrap();
 }
 
 #[monoio::test_all]
 async fn test_query_syntax_error() {
     let mut client = get_client().await;
-    let result = client.query("SELECT * FROM nonexistent_table").await;
+    let result = client.query("SELECT * FROM nonexistent_table", &[]).await;
     assert!(result.is_err());
 
     // Connection should still be usable after an error in simple query protocol!
     // (Postgres automatically goes back to ReadyForQuery).
-    let rows = client.query("SELECT 1").await.unwrap();
+    let rows = client.query("SELECT 1", &[]).await.unwrap();
     assert_eq!(rows.len(), 1);
 }
 
@@ -136,13 +156,248 @@ async fn test_pool() {
     let pool = Pool::new(HOST, USER, Some(PASS), Some("postgres"));
 
     let mut client1 = pool.get().await.unwrap();
-    client1.execute("SELECT 1").await.unwrap();
+    client1.execute("SELECT 1", &[]).await.unwrap();
 
-    pool.put(client1);
+    drop(client1);
 
     // Should reuse the connection
     let mut client2 = pool.get().await.unwrap();
-    client2.execute("SELECT 2").await.unwrap();
+    client2.execute("SELECT 2", &[]).await.unwrap();
+    assert_eq!(pool.size(), 1);
+}
+
+async fn backend_pid(client: &mut Client) -> i32 {
+    let rows = client.query("SELECT pg_backend_pid()", &[]).await.unwrap();
+    rows[0].get(0)
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_pool_max_size_waiters() {
+    let pool = Pool::builder(config()).max_size(1).build();
+
+    let mut first = pool.get().await.unwrap();
+    let pid = backend_pid(&mut first).await;
+
+    let order = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut handles = Vec::new();
+    for i in 0..3 {
+        let pool = pool.clone();
+        let order = order.clone();
+        handles.push(monoio::spawn(async move {
+            let mut client = pool.get().await.unwrap();
+            order.borrow_mut().push(i);
+            backend_pid(&mut client).await
+        }));
+    }
+    // Let the tasks queue up behind the checked-out client.
+    monoio::time::sleep(std::time::Duration::from_millis(20)).await;
+    assert!(order.borrow().is_empty());
+
+    drop(first);
+    for handle in handles {
+        assert_eq!(handle.await, pid);
+    }
+    assert_eq!(*order.borrow(), vec![0, 1, 2]);
+    assert_eq!(pool.size(), 1);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_pool_acquire_timeout() {
+    let pool = Pool::builder(config())
+        .max_size(1)
+        .acquire_timeout(std::time::Duration::from_secs(1))
+        .build();
+
+    let client = pool.get().await.unwrap();
+    assert!(matches!(pool.get().await, Err(Error::Timeout(_))));
+
+    // The timed-out waiter must not hold on to the slot.
+    drop(client);
+    pool.get().await.unwrap();
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_pool_acquire_timeout_while_connecting() {
+    // A listener that never accepts, so opening a connection hangs.
+    let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut config = config();
+    config
+        .host("127.0.0.1")
+        .port(silent.local_addr().unwrap().port());
+    let pool = Pool::builder(config)
+        .max_size(1)
+        .acquire_timeout(std::time::Duration::from_millis(100))
+        .build();
+
+    for _ in 0..2 {
+        assert!(matches!(pool.get().await, Err(Error::Timeout(_))));
+        assert_eq!(pool.size(), 0);
+    }
+    pool.close().await;
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_pool_acquire_timeout_during_test_query() {
+    let pool = Pool::builder(config())
+        .max_size(1)
+        .acquire_timeout(std::time::Duration::from_millis(200))
+        .test_query("SELECT pg_sleep(1)")
+        .build();
+
+    // A new connection is not tested; an idle one is.
+    drop(pool.get().await.unwrap());
+    assert!(matches!(pool.get().await, Err(Error::Timeout(_))));
+    assert_eq!(pool.size(), 0);
+    assert_eq!(pool.idle(), 0);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_pool_idle_timeout() {
+    let pool = Pool::builder(config())
+        .idle_timeout(std::time::Duration::from_millis(20))
+        .build();
+
+    let pid = backend_pid(&mut pool.get().await.unwrap()).await;
+    monoio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    let new_pid = backend_pid(&mut pool.get().await.unwrap()).await;
+    assert_ne!(pid, new_pid);
+    assert_eq!(pool.size(), 1);
+}
+
+#[monoio::test_all]
+async fn test_pool_discards_broken_connection() {
+    let pool = Pool::builder(config()).test_query("SELECT 1").build();
+
+    let mut client = pool.get().await.unwrap();
+    let pid = backend_pid(&mut client).await;
+    get_client()
+        .await
+        .execute("SELECT pg_terminate_backend($1)", &[&pid])
+        .await
+        .unwrap();
+    assert!(client.execute("SELECT 1", &[]).await.is_err());
+    assert!(client.is_closed());
+    assert!(matches!(
+        client.execute("SELECT 1", &[]).await,
+        Err(Error::Broken)
+    ));
+    drop(client);
+    assert_eq!(pool.size(), 0);
+
+    let new_pid = backend_pid(&mut pool.get().await.unwrap()).await;
+    assert_ne!(pid, new_pid);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_dropped_query_closes_client() {
+    let pool = Pool::builder(config()).build();
+
+    let mut client = pool.get().await.unwrap();
+    assert!(!client.is_closed());
+    let res = monoio::time::timeout(
+        std::time::Duration::from_millis(100),
+        client.query("SELECT pg_sleep(2)", &[]),
+    )
+    .await;
+    assert!(res.is_err());
+
+    // The read that was cut short may have lost part of the response.
+    assert!(client.is_closed());
+    assert!(matches!(
+        client.query("SELECT 1", &[]).await,
+        Err(Error::Broken)
+    ));
+    drop(client);
+    assert_eq!(pool.size(), 0);
+
+    let rows = pool
+        .get()
+        .await
+        .unwrap()
+        .query("SELECT 1", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_client_close() {
+    let mut client = get_client().await;
+    let pid = backend_pid(&mut client).await;
+    client.close().await.unwrap();
+
+    // The backend exits cleanly once it has read `Terminate`.
+    let mut other = get_client().await;
+    for _ in 0..50 {
+        let rows = other
+            .query("SELECT 1 FROM pg_stat_activity WHERE pid = $1", &[&pid])
+            .await
+            .unwrap();
+        if rows.is_empty() {
+            return;
+        }
+        monoio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("backend {} is still running", pid);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_pool_close() {
+    let pool = Pool::builder(config()).build();
+    drop(pool.get().await.unwrap());
+    assert_eq!(pool.idle(), 1);
+    pool.close().await;
+    assert_eq!(pool.size(), 0);
+    assert!(matches!(pool.get().await, Err(Error::Closed)));
+
+    let pool = Pool::builder(config()).max_size(2).build();
+    let mut a = pool.get().await.unwrap();
+    let b = pool.get().await.unwrap();
+    let waiting = monoio::spawn({
+        let pool = pool.clone();
+        async move { pool.get().await.map(|_| ()) }
+    });
+    monoio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+    // Closing doesn't wait for checked-out clients, so holding one is fine.
+    pool.close().await;
+    assert!(matches!(waiting.await, Err(Error::Closed)));
+    assert_eq!(pool.size(), 2);
+
+    // Returned clients are closed instead of going back to the pool.
+    let pid = backend_pid(&mut a).await;
+    drop(a);
+    assert_eq!(pool.size(), 1);
+    assert_eq!(pool.idle(), 0);
+    drop(b);
+    assert_eq!(pool.size(), 0);
+
+    let mut other = get_client().await;
+    for _ in 0..50 {
+        let rows = other
+            .query("SELECT 1 FROM pg_stat_activity WHERE pid = $1", &[&pid])
+            .await
+            .unwrap();
+        if rows.is_empty() {
+            return;
+        }
+        monoio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    panic!("backend {} is still running", pid);
+}
+
+#[monoio::test_all]
+async fn test_pool_instances_are_separate() {
+    let a = Pool::new(HOST, USER, Some(PASS), Some("postgres"));
+    let b = Pool::new(HOST, USER, Some(PASS), Some("postgres"));
+
+    drop(a.get().await.unwrap());
+    assert_eq!(a.idle(), 1);
+    assert_eq!(b.idle(), 0);
+    let detached = a.get().await.unwrap().detach();
+    assert_eq!(a.size(), 0);
+    drop(detached);
 }
 
 #[monoio::test_all]
@@ -151,14 +406,1867 @@ async fn test_data_types() {
     // Postgres boolean is 't' or 'f' but wait, query returns text format integers and bools.
     // 't' is length 1 in text format. Our string decoding should handle "t".
     let rows = client
-        .query("SELECT true::boolean, false::boolean")
+        .query("SELECT true::boolean, false::boolean", &[])
         .await
         .unwrap();
 
     // For binary format, boolean is bool
-    let t_val: bool = rows[0].get(0).unwrap();
-    let f_val: bool = rows[0].get(1).unwrap();
+    let t_val: bool = rows[0].get(0);
+    let f_val: bool = rows[0].get(1);
+
+    assert!(t_val);
+    assert!(!f_val);
+}
+
+#[monoio::test_all]
+async fn test_query_params() {
+    let mut client = get_client().await;
+    let rows = client
+        .query(
+            "SELECT $1::INT4 + 1, $2::TEXT, $3::INT8",
+            &[&41i32, &"hello", &None::<i64>],
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+
+    let num: i32 = rows[0].get(0);
+    let str_val: String = rows[0].get(1);
+    let null_val: Option<i64> = rows[0].get(2);
+
+    assert_eq!(num, 42);
+    assert_eq!(str_val, "hello");
+    assert_eq!(null_val, None);
+}
+
+#[monoio::test_all]
+async fn test_row_get_by_name() {
+    let mut client = get_client().await;
+    let rows = client
+        .query(
+            "SELECT 1::INT4 AS id, 'alice' AS \"Name\", NULL::TEXT AS email",
+            &[],
+        )
+        .await
+        .unwrap();
+    let row = &rows[0];
+
+    assert_eq!(row.len(), 3);
+    assert_eq!(row.columns()[1].name, "Name");
+    assert_eq!(row.get::<_, i32>("id"), 1);
+    assert_eq!(row.get::<_, &str>("Name"), "alice");
+    assert_eq!(row.get::<_, &str>("name"), "alice");
+    assert_eq!(row.get::<_, Option<String>>("email"), None);
+    let name = String::from("id");
+    assert_eq!(row.get::<_, i32>(&name), 1);
+
+    assert!(matches!(
+        row.try_get::<_, i32>("missing"),
+        Err(Error::Parse(_))
+    ));
+    assert!(matches!(row.try_get::<_, i32>(3), Err(Error::Parse(_))));
+    // INT4 is not accepted by i64, nor TEXT by i32.
+    assert!(matches!(row.try_get::<_, i64>("id"), Err(Error::Parse(_))));
+    assert!(matches!(row.try_get::<_, i32>(1), Err(Error::Parse(_))));
+    // NULL needs an Option.
+    assert!(row.try_get::<_, String>("email").is_err());
+}
+
+#[monoio::test_all]
+#[should_panic(expected = "error retrieving column missing")]
+async fn test_row_get_panics() {
+    let mut client = get_client().await;
+    let rows = client.query("SELECT 1", &[]).await.unwrap();
+    rows[0].get::<_, i32>("missing");
+}
+
+#[monoio::test_all]
+async fn test_execute_params() {
+    let mut client = get_client().await;
+    let _ = client
+        .execute("DROP TABLE IF EXISTS test_params", &[])
+        .await;
+    client
+        .execute("CREATE TABLE test_params (id INT, name TEXT)", &[])
+        .await
+        .unwrap();
+
+    for (id, name) in [(1i32, "one"), (2, "two; DROP TABLE test_params")] {
+        client
+            .execute(
+                "INSERT INTO test_params (id, name) VALUES ($1, $2)",
+                &[&id, &name],
+            )
+            .await
+            .unwrap();
+    }
+
+    let rows = client
+        .query("SELECT name FROM test_params WHERE id = $1", &[&2i32])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    let name: String = rows[0].get(0);
+    assert_eq!(name, "two; DROP TABLE test_params");
+
+    client.execute("DROP TABLE test_params", &[]).await.unwrap();
+}
+
+#[monoio::test_all]
+async fn test_params_mismatch() {
+    let mut client = get_client().await;
+
+    let result = client.query("SELECT $1::INT4", &[]).await;
+    assert!(matches!(result, Err(Error::Parameter(_))));
+
+    let result = client.query("SELECT $1::INT4", &[&"not an int"]).await;
+    assert!(matches!(result, Err(Error::Parameter(_))));
+
+    // Nothing was sent for the rejected binds, so the connection is still in sync.
+    let rows = client.query("SELECT $1::INT4", &[&7i32]).await.unwrap();
+    let num: i32 = rows[0].get(0);
+    assert_eq!(num, 7);
+}
+
+#[monoio::test_all]
+async fn test_batch_execute() {
+    let mut client = get_client().await;
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS test_batch; CREATE TABLE test_batch (id INT); \
+             INSERT INTO test_batch VALUES (1), (2);",
+        )
+        .await
+        .unwrap();
+
+    let rows = client
+        .query("SELECT id FROM test_batch ORDER BY id", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+
+    client.batch_execute("DROP TABLE test_batch").await.unwrap();
+}
+
+#[monoio::test_all]
+async fn test_simple_query() {
+    let mut client = get_client().await;
+    let results = client
+        .simple_query(
+            "CREATE TEMP TABLE test_simple (id INT, name TEXT); \
+             INSERT INTO test_simple VALUES (1, 'a'), (2, NULL); \
+             SELECT id, name FROM test_simple ORDER BY id; \
+             SELECT 1 WHERE false",
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0].tag, CommandTag::Other("CREATE TABLE".into()));
+    assert!(results[0].columns.is_empty());
+    assert_eq!(results[1].tag.rows(), 2);
+
+    let select = &results[2];
+    assert_eq!(select.tag, CommandTag::Select(2));
+    assert_eq!(select.columns[1].name, "name");
+    assert_eq!(select.rows.len(), 2);
+    assert_eq!(select.rows[0].len(), 2);
+    assert_eq!(select.rows[0].get(0), Some("1"));
+    assert_eq!(select.rows[0].get("name"), Some("a"));
+    assert_eq!(select.rows[1].get("name"), None);
+    assert!(matches!(
+        select.rows[1].try_get("missing"),
+        Err(Error::Parse(_))
+    ));
+
+    assert_eq!(results[3].columns.len(), 1);
+    assert!(results[3].rows.is_empty());
+
+    // Nothing after the failing statement runs.
+    let err = client
+        .simple_query("SELECT 1; SELECT 1/0; INSERT INTO test_simple VALUES (3, 'c')")
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Some(&SqlState::DivisionByZero));
+    let results = client
+        .simple_query("SELECT count(*) FROM test_simple")
+        .await
+        .unwrap();
+    assert_eq!(results[0].rows[0].get(0), Some("2"));
+
+    assert!(client.simple_query("").await.unwrap().is_empty());
+}
+
+#[monoio::test_all]
+async fn test_db_error_fields() {
+    let mut client = get_client().await;
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS test_db_error; \
+             CREATE TABLE test_db_error (id INT CONSTRAINT test_db_error_pkey PRIMARY KEY); \
+             INSERT INTO test_db_error VALUES (1);",
+        )
+        .await
+        .unwrap();
+
+    let err = client
+        .execute("INSERT INTO test_db_error VALUES ($1)", &[&1i32])
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::UniqueViolation));
+    let db = err.as_db_error().unwrap();
+    assert_eq!(db.severity, "ERROR");
+    assert_eq!(db.table.as_deref(), Some("test_db_error"));
+    assert_eq!(db.constraint.as_deref(), Some("test_db_error_pkey"));
+    assert!(db.detail.is_some());
+
+    let err = client.query("SELECT * FROM", &[]).await.err().unwrap();
+    assert_eq!(err.code(), Some(&SqlState::SyntaxError));
+    assert_eq!(
+        err.as_db_error().unwrap().position,
+        Some(ErrorPosition::Original(14))
+    );
+
+    client
+        .batch_execute("DROP TABLE test_db_error")
+        .await
+        .unwrap();
+}
+
+#[monoio::test_all]
+async fn test_connect_wrong_password_code() {
+    let err = Client::connect(HOST, USER, Some("wrongpassword"), None)
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Some(&SqlState::InvalidPassword));
+}
+
+#[monoio::test_all]
+async fn test_execute_row_count() {
+    let mut client = get_client().await;
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS test_row_count; CREATE TABLE test_row_count (id INT, val TEXT);",
+        )
+        .await
+        .unwrap();
+
+    let n = client
+        .execute(
+            "INSERT INTO test_row_count VALUES (1, 'a'), (2, 'b'), (3, 'c')",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(n, 3);
+
+    let n = client
+        .execute(
+            "UPDATE test_row_count SET val = 'x' WHERE id > $1",
+            &[&1i32],
+        )
+        .await
+        .unwrap();
+    assert_eq!(n, 2);
+
+    let n = client
+        .execute(
+            "UPDATE test_row_count SET val = 'y' WHERE id = $1",
+            &[&42i32],
+        )
+        .await
+        .unwrap();
+    assert_eq!(n, 0);
+
+    let (rows, tag) = client
+        .query_with_tag("DELETE FROM test_row_count WHERE id <= 2 RETURNING id", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(tag, CommandTag::Delete(2));
+
+    let (_, tag) = client
+        .query_with_tag("SELECT * FROM test_row_count", &[])
+        .await
+        .unwrap();
+    assert_eq!(tag, CommandTag::Select(1));
+
+    client
+        .batch_execute("DROP TABLE test_row_count")
+        .await
+        .unwrap();
+}
+
+#[monoio::test_all]
+async fn test_connect_with_config() {
+    let config: Config = format!(
+        "postgres://{}:{}@{}/postgres?application_name=monoio-test&search_path=pg_catalog",
+        USER, PASS, HOST
+    )
+    .parse()
+    .unwrap();
+    let mut client = Client::connect_with_config(&config).await.unwrap();
+
+    let rows = client
+        .query(
+            "SELECT current_setting('application_name'), current_setting('search_path')",
+            &[],
+        )
+        .await
+        .unwrap();
+    let app: String = rows[0].get(0);
+    let search_path: String = rows[0].get(1);
+    assert_eq!(app, "monoio-test");
+    assert_eq!(search_path, "pg_catalog");
+
+    let pool = Pool::with_config(config);
+    pool.get().await.unwrap();
+}
+
+async fn count_rows(client: &mut Client, table: &str) -> i64 {
+    let rows = client
+        .query(&format!("SELECT count(*) FROM {}", table), &[])
+        .await
+        .unwrap();
+    rows[0].get(0)
+}
+
+#[monoio::test_all]
+async fn test_transaction_commit_rollback() {
+    let mut client = get_client().await;
+    client
+        .batch_execute("CREATE TEMP TABLE test_tx (id INT)")
+        .await
+        .unwrap();
+
+    let mut tx = client.transaction().await.unwrap();
+    tx.execute("INSERT INTO test_tx VALUES ($1)", &[&1i32])
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!(count_rows(&mut client, "test_tx").await, 1);
+
+    let mut tx = client.transaction().await.unwrap();
+    tx.execute("INSERT INTO test_tx VALUES ($1)", &[&2i32])
+        .await
+        .unwrap();
+    tx.rollback().await.unwrap();
+    assert_eq!(count_rows(&mut client, "test_tx").await, 1);
+}
+
+#[monoio::test_all]
+async fn test_transaction_drop_rolls_back() {
+    let mut client = get_client().await;
+    client
+        .batch_execute("CREATE TEMP TABLE test_tx_drop (id INT)")
+        .await
+        .unwrap();
+
+    {
+        let mut tx = client.transaction().await.unwrap();
+        tx.execute("INSERT INTO test_tx_drop VALUES (1)", &[])
+            .await
+            .unwrap();
+        // A failed statement leaves the transaction aborted; the rollback
+        // on drop must still get the connection usable again.
+        assert!(tx.execute("SELECT 1/0", &[]).await.is_err());
+    }
+    assert_eq!(count_rows(&mut client, "test_tx_drop").await, 0);
+
+    // A new transaction can start right away, so the old one is gone.
+    let tx = client.transaction().await.unwrap();
+    tx.commit().await.unwrap();
+}
+
+#[monoio::test_all]
+async fn test_transaction_savepoints() {
+    let mut client = get_client().await;
+    client
+        .batch_execute("CREATE TEMP TABLE test_tx_sp (id INT)")
+        .await
+        .unwrap();
+
+    let mut tx = client.transaction().await.unwrap();
+    tx.execute("INSERT INTO test_tx_sp VALUES (1)", &[])
+        .await
+        .unwrap();
+
+    let mut sp = tx.transaction().await.unwrap();
+    sp.execute("INSERT INTO test_tx_sp VALUES (2)", &[])
+        .await
+        .unwrap();
+    sp.rollback().await.unwrap();
+
+    {
+        let mut sp = tx.transaction().await.unwrap();
+        sp.execute("INSERT INTO test_tx_sp VALUES (3)", &[])
+            .await
+            .unwrap();
+        let mut inner = sp.transaction().await.unwrap();
+        inner
+            .execute("INSERT INTO test_tx_sp VALUES (4)", &[])
+            .await
+            .unwrap();
+        // Dropping the innermost savepoint only undoes its own insert.
+        drop(inner);
+        sp.commit().await.unwrap();
+    }
+
+    let mut sp = tx.transaction().await.unwrap();
+    assert!(sp.execute("SELECT 1/0", &[]).await.is_err());
+    drop(sp);
+
+    tx.commit().await.unwrap();
+
+    let rows = client
+        .query("SELECT id FROM test_tx_sp ORDER BY id", &[])
+        .await
+        .unwrap();
+    let ids: Vec<i32> = rows.iter().map(|r| r.get(0)).collect();
+    assert_eq!(ids, vec![1, 3]);
+}
+
+#[monoio::test_all]
+async fn test_transaction_builder() {
+    let mut client = get_client().await;
+    let mut tx = client
+        .build_transaction()
+        .isolation_level(IsolationLevel::Serializable)
+        .read_only(true)
+        .deferrable(true)
+        .start()
+        .await
+        .unwrap();
+    let rows = tx
+        .query("SELECT current_setting('transaction_isolation')", &[])
+        .await
+        .unwrap();
+    let level: String = rows[0].get(0);
+    assert_eq!(level, "serializable");
+
+    let err = tx
+        .execute("CREATE TABLE test_tx_ro (id INT)", &[])
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::ReadOnlySqlTransaction));
+}
+
+#[monoio::test_all]
+async fn test_transaction_drop_before_pool_return() {
+    let pool = Pool::new(HOST, USER, Some(PASS), Some("postgres"));
+    let mut client = pool.get().await.unwrap();
+
+    let mut tx = client.transaction().await.unwrap();
+    tx.batch_execute("SET LOCAL application_name = 'in_tx'")
+        .await
+        .unwrap();
+    drop(tx);
+    drop(client);
+
+    let mut client = pool.get().await.unwrap();
+    let rows = client
+        .query("SELECT current_setting('application_name')", &[])
+        .await
+        .unwrap();
+    let name: String = rows[0].get(0);
+    assert_ne!(name, "in_tx");
+}
+
+#[monoio::test_all]
+async fn test_query_stream() {
+    let mut client = get_client().await;
+
+    let mut stream = client
+        .query_stream("SELECT generate_series(1, $1)", &[&100i32])
+        .await
+        .unwrap();
+    let mut sum = 0;
+    while let Some(row) = stream.next().await {
+        let n: i32 = row.unwrap().get(0);
+        sum += n;
+    }
+    assert_eq!(sum, 5050);
+    assert_eq!(stream.command_tag(), Some(&CommandTag::Select(100)));
+}
+
+#[monoio::test_all]
+async fn test_query_stream_bounded() {
+    let mut client = get_client().await;
+
+    let mut stream = client
+        .query_stream_bounded("SELECT generate_series(1, 10)", &[], 3)
+        .await
+        .unwrap();
+    let mut seen = Vec::new();
+    while let Some(row) = stream.next().await {
+        seen.push(row.unwrap().get::<_, i32>(0));
+    }
+    assert_eq!(seen, (1..=10).collect::<Vec<_>>());
+    assert_eq!(stream.command_tag(), Some(&CommandTag::Select(10)));
+    drop(stream);
+
+    assert!(
+        client
+            .query_stream_bounded("SELECT 1", &[], 0)
+            .await
+            .is_err()
+    );
+}
+
+#[monoio::test_all]
+async fn test_query_stream_dropped_early() {
+    let mut client = get_client().await;
+
+    for max_rows in [0, 5] {
+        let query = "SELECT generate_series(1, 1000)";
+        let mut stream = if max_rows == 0 {
+            client.query_stream(query, &[]).await.unwrap()
+        } else {
+            client
+                .query_stream_bounded(query, &[], max_rows)
+                .await
+                .unwrap()
+        };
+        let first: i32 = stream.next().await.unwrap().unwrap().get(0);
+        assert_eq!(first, 1);
+        drop(stream);
+
+        // The rest of the abandoned result must not leak into the next query.
+        let rows = client.query("SELECT 'after'", &[]).await.unwrap();
+        let s: String = rows[0].get(0);
+        assert_eq!(s, "after");
+    }
+}
+
+#[monoio::test_all]
+async fn test_query_stream_error() {
+    let mut client = get_client().await;
+
+    let mut stream = client
+        .query_stream_bounded("SELECT 10 / (5 - generate_series(1, 10))", &[], 2)
+        .await
+        .unwrap();
+    let mut ok = 0;
+    let mut err = None;
+    while let Some(row) = stream.next().await {
+        match row {
+            Ok(_) => ok += 1,
+            Err(e) => err = Some(e),
+        }
+    }
+    assert_eq!(ok, 4);
+    assert_eq!(err.unwrap().code(), Some(&SqlState::DivisionByZero));
+    drop(stream);
+
+    let rows = client.query("SELECT 1", &[]).await.unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[monoio::test_all]
+async fn test_query_stream_in_transaction() {
+    let mut client = get_client().await;
+    let mut tx = client.transaction().await.unwrap();
+    tx.batch_execute("CREATE TEMP TABLE test_stream_tx AS SELECT generate_series(1, 7) AS id")
+        .await
+        .unwrap();
+
+    let mut stream = tx
+        .query_stream_bounded("SELECT id FROM test_stream_tx", &[], 2)
+        .await
+        .unwrap();
+    let mut count = 0;
+    while let Some(row) = stream.next().await {
+        row.unwrap();
+        count += 1;
+    }
+    assert_eq!(count, 7);
+    drop(stream);
+
+    // The bounded stream's Sync must not have ended the transaction.
+    tx.rollback().await.unwrap();
+    assert!(
+        client
+            .query("SELECT * FROM test_stream_tx", &[])
+            .await
+            .is_err()
+    );
+}
+
+#[monoio::test_all]
+async fn test_copy_in_text_and_csv() {
+    let mut client = get_client().await;
+    client
+        .batch_execute("CREATE TEMP TABLE test_copy_text (id INT, name TEXT)")
+        .await
+        .unwrap();
+
+    let mut sink = client
+        .copy_in("COPY test_copy_text FROM STDIN")
+        .await
+        .unwrap();
+    sink.send(b"1\tplain\n").await.unwrap();
+    sink.send_text_row(&[Some("2"), Some("tab\there\\ and\nnewline")])
+        .await
+        .unwrap();
+    sink.send_text_row(&[Some("3"), None]).await.unwrap();
+    assert_eq!(sink.finish().await.unwrap(), 3);
+
+    let mut sink = client
+        .copy_in("COPY test_copy_text FROM STDIN (FORMAT csv)")
+        .await
+        .unwrap();
+    sink.send_csv_row(&[Some("4"), Some("say \"hi\", bye")])
+        .await
+        .unwrap();
+    sink.send_csv_row(&[Some("5"), Some("")]).await.unwrap();
+    sink.send_csv_row(&[Some("6"), None]).await.unwrap();
+    assert_eq!(sink.finish().await.unwrap(), 3);
+
+    let rows = client
+        .query("SELECT name FROM test_copy_text ORDER BY id", &[])
+        .await
+        .unwrap();
+    let names: Vec<Option<String>> = rows.iter().map(|r| r.get(0)).collect();
+    assert_eq!(
+        names,
+        vec![
+            Some("plain".to_string()),
+            Some("tab\there\\ and\nnewline".to_string()),
+            None,
+            Some("say \"hi\", bye".to_string()),
+            Some(String::new()),
+            None,
+        ]
+    );
+}
+
+#[monoio::test_all]
+async fn test_copy_in_binary() {
+    let mut client = get_client().await;
+    client
+        .batch_execute("CREATE TEMP TABLE test_copy_bin (id INT8, name TEXT)")
+        .await
+        .unwrap();
+
+    let sink = client
+        .copy_in("COPY test_copy_bin FROM STDIN (FORMAT binary)")
+        .await
+        .unwrap();
+    let mut writer = sink.binary(&[Type::INT8, Type::TEXT]);
+    for i in 0..20_000i64 {
+        let name = if i % 2 == 0 {
+            Some(format!("row {}", i))
+        } else {
+            None
+        };
+        writer.write(&[&i, &name]).await.unwrap();
+    }
+    assert!(writer.write(&[&1i64]).await.is_err());
+    assert!(writer.write(&[&1i32, &"x"]).await.is_err());
+    assert_eq!(writer.finish().await.unwrap(), 20_000);
+
+    let rows = client
+        .query(
+            "SELECT count(*), count(name), sum(id)::INT8 FROM test_copy_bin",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i64>(0), 20_000);
+    assert_eq!(rows[0].get::<_, i64>(1), 10_000);
+    assert_eq!(rows[0].get::<_, i64>(2), 199_990_000);
+}
+
+#[monoio::test_all]
+async fn test_copy_in_abort_and_errors() {
+    let mut client = get_client().await;
+    client
+        .batch_execute("CREATE TEMP TABLE test_copy_abort (id INT)")
+        .await
+        .unwrap();
+
+    let mut sink = client
+        .copy_in("COPY test_copy_abort FROM STDIN")
+        .await
+        .unwrap();
+    sink.send(b"1\n").await.unwrap();
+    sink.abort("changed my mind").await.unwrap();
+
+    let mut sink = client
+        .copy_in("COPY test_copy_abort FROM STDIN")
+        .await
+        .unwrap();
+    sink.send(b"2\n").await.unwrap();
+    drop(sink);
+
+    let mut sink = client
+        .copy_in("COPY test_copy_abort FROM STDIN")
+        .await
+        .unwrap();
+    sink.send(b"not a number\n").await.unwrap();
+    let err = sink.finish().await.unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::InvalidTextRepresentation));
+
+    let err = client
+        .copy_in("COPY no_such_table FROM STDIN")
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Some(&SqlState::UndefinedTable));
+    assert!(client.copy_in("SELECT 1").await.is_err());
+
+    assert_eq!(count_rows(&mut client, "test_copy_abort").await, 0);
+}
+
+#[monoio::test_all]
+async fn test_copy_out_text() {
+    let mut client = get_client().await;
+
+    let mut stream = client
+        .copy_out("COPY (SELECT n, 'row ' || n FROM generate_series(1, 3) n) TO STDOUT")
+        .await
+        .unwrap();
+    let mut data = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        data.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(data, b"1\trow 1\n2\trow 2\n3\trow 3\n");
+    assert_eq!(stream.command_tag(), Some(&CommandTag::Copy(3)));
+}
+
+#[monoio::test_all]
+async fn test_copy_out_binary() {
+    let mut client = get_client().await;
+
+    let stream = client
+        .copy_out(
+            "COPY (SELECT n::INT8, CASE WHEN n % 2 = 0 THEN 'even' END \
+             FROM generate_series(1, 1000) n) TO STDOUT (FORMAT binary)",
+        )
+        .await
+        .unwrap();
+    let mut rows = stream.binary(&[Type::INT8, Type::TEXT]);
+    let mut sum = 0;
+    let mut evens = 0;
+    while let Some(row) = rows.next().await {
+        let row = row.unwrap();
+        assert_eq!(row.len(), 2);
+        sum += row.get::<i64>(0).unwrap();
+        if row.get::<Option<&str>>(1).unwrap() == Some("even") {
+            evens += 1;
+        }
+        assert!(row.get::<i32>(0).is_err());
+    }
+    assert_eq!(sum, 500_500);
+    assert_eq!(evens, 500);
+    assert_eq!(rows.command_tag(), Some(&CommandTag::Copy(1000)));
+}
+
+#[monoio::test_all]
+async fn test_copy_out_binary_ends_after_error() {
+    let mut client = get_client().await;
+
+    let stream = client
+        .copy_out("COPY (SELECT 1::INT8, 2::INT8) TO STDOUT (FORMAT binary)")
+        .await
+        .unwrap();
+    // One type for two columns.
+    let mut rows = stream.binary(&[Type::INT8]);
+    assert!(rows.next().await.unwrap().is_err());
+    assert!(rows.next().await.is_none());
+    assert!(rows.next().await.is_none());
+    drop(rows);
+
+    // The rest of the data is discarded before the next query.
+    let rows = client.query("SELECT 1", &[]).await.unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+}
+
+#[monoio::test_all]
+async fn test_copy_out_dropped_and_errors() {
+    let mut client = get_client().await;
+
+    let mut stream = client
+        .copy_out("COPY (SELECT generate_series(1, 10000)) TO STDOUT")
+        .await
+        .unwrap();
+    stream.next().await.unwrap().unwrap();
+    drop(stream);
+
+    let err = client
+        .copy_out("COPY no_such_table TO STDOUT")
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Some(&SqlState::UndefinedTable));
+
+    let mut stream = client
+        .copy_out("COPY (SELECT 10 / (3 - generate_series(1, 5))) TO STDOUT")
+        .await
+        .unwrap();
+    let mut err = None;
+    while let Some(chunk) = stream.next().await {
+        if let Err(e) = chunk {
+            err = Some(e);
+        }
+    }
+    assert_eq!(err.unwrap().code(), Some(&SqlState::DivisionByZero));
+    drop(stream);
+
+    let rows = client.query("SELECT 'after'", &[]).await.unwrap();
+    let s: String = rows[0].get(0);
+    assert_eq!(s, "after");
+}
+
+#[monoio::test_all]
+async fn test_pipeline() {
+    let mut client = get_client().await;
+    client
+        .batch_execute("CREATE TEMP TABLE pipeline_test (id INT PRIMARY KEY)")
+        .await
+        .unwrap();
+
+    let mut pipeline = client.pipeline();
+    for i in 0..3 {
+        pipeline
+            .query("INSERT INTO pipeline_test VALUES ($1)", &[&i])
+            .await
+            .unwrap();
+    }
+    // Fails at execution time; the queries around it must still run.
+    pipeline
+        .query("INSERT INTO pipeline_test VALUES ($1)", &[&1i32])
+        .await
+        .unwrap();
+    pipeline
+        .query("SELECT count(*) FROM pipeline_test", &[])
+        .await
+        .unwrap();
+    assert_eq!(pipeline.len(), 5);
+
+    let results = pipeline.run().await.unwrap();
+    assert_eq!(results.len(), 5);
+    for result in &results[..3] {
+        assert_eq!(result.as_ref().unwrap().1.rows(), 1);
+    }
+    assert_eq!(
+        results[3].as_ref().err().unwrap().code(),
+        Some(&SqlState::UniqueViolation)
+    );
+    let (rows, _) = results[4].as_ref().unwrap();
+    let count: i64 = rows[0].get(0);
+    assert_eq!(count, 3);
+
+    // The connection is in sync afterwards.
+    assert!(client.pipeline().run().await.unwrap().is_empty());
+    assert_eq!(client.execute("SELECT 1", &[]).await.unwrap(), 1);
+}
+
+#[monoio::test_all]
+async fn test_pipeline_rejected_query() {
+    let mut client = get_client().await;
+
+    let mut pipeline = client.pipeline();
+    pipeline.query("SELECT $1::int4", &[&7i32]).await.unwrap();
+    let err = pipeline.query("SELEC 1", &[]).await.unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::SyntaxError));
+    let err = pipeline
+        .query("SELECT $1::int4", &[&"text"])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Parameter(_)));
+    assert_eq!(pipeline.len(), 1);
+
+    let results = pipeline.run().await.unwrap();
+    let value: i32 = results[0].as_ref().unwrap().0[0].get(0);
+    assert_eq!(value, 7);
+}
+
+async fn is_prepared(client: &mut Client, query: &str) -> bool {
+    let rows = client
+        .query(
+            "SELECT count(*) FROM pg_prepared_statements WHERE statement = $1",
+            &[&query],
+        )
+        .await
+        .unwrap();
+    rows[0].get::<_, i64>(0) > 0
+}
+
+#[monoio::test_all]
+async fn test_prepare() {
+    let mut client = get_client().await;
+
+    let query = "SELECT $1::int4 + 1 AS next, $2::text AS label";
+    let stmt = client.prepare(query).await.unwrap();
+    assert_eq!(stmt.params(), &[Type::INT4, Type::TEXT]);
+    let names: Vec<_> = stmt.columns().iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["next", "label"]);
+
+    let rows = client.query(&stmt, &[&41i32, &"x"]).await.unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 42);
+    assert_eq!(client.execute(&stmt, &[&1i32, &"y"]).await.unwrap(), 1);
+    assert!(matches!(
+        client.query(&stmt, &[&1i32]).await,
+        Err(Error::Parameter(_))
+    ));
+
+    let mut stream = client.query_stream(&stmt, &[&1i32, &"z"]).await.unwrap();
+    assert!(stream.next().await.unwrap().is_ok());
+    assert!(stream.next().await.is_none());
+    drop(stream);
+
+    let stmt = client
+        .prepare_typed("SELECT $1", &[Type::INT8])
+        .await
+        .unwrap();
+    assert_eq!(stmt.params(), &[Type::INT8]);
+    let rows = client.query(&stmt, &[&7i64]).await.unwrap();
+    assert_eq!(rows[0].get::<_, i64>(0), 7);
+
+    assert_eq!(
+        client.prepare("SELEC 1").await.err().unwrap().code(),
+        Some(&SqlState::SyntaxError)
+    );
+}
+
+#[monoio::test_all]
+async fn test_statement_from_other_connection() {
+    let mut a = get_client().await;
+    let mut b = get_client().await;
+
+    let stmt = a.prepare("SELECT 1::int4").await.unwrap();
+    b.prepare("SELECT 2::int4").await.unwrap();
+    assert!(matches!(
+        b.query(&stmt, &[]).await,
+        Err(Error::Parameter(_))
+    ));
+
+    let rows = b.query("SELECT 3::int4", &[]).await.unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 3);
+    let rows = a.query(&stmt, &[]).await.unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+}
+
+#[monoio::test_all]
+async fn test_statement_closed_on_drop() {
+    let mut client = get_client().await;
+
+    let query = "SELECT 'closed on drop'";
+    let stmt = client.prepare(query).await.unwrap();
+    let clone = stmt.clone();
+    drop(stmt);
+    assert!(is_prepared(&mut client, query).await);
+    drop(clone);
+    assert!(!is_prepared(&mut client, query).await);
+
+    let query = "SELECT 'closed explicitly'";
+    let stmt = client.prepare(query).await.unwrap();
+    client.close_statement(stmt).await.unwrap();
+    assert!(!is_prepared(&mut client, query).await);
+
+    let mut tx = client.transaction().await.unwrap();
+    let stmt = tx.prepare("SELECT 1").await.unwrap();
+    assert_eq!(tx.query(&stmt, &[]).await.unwrap().len(), 1);
+    drop(stmt);
+    tx.commit().await.unwrap();
+}
+
+async fn cached_lru_statements(client: &mut Client) -> Vec<String> {
+    client
+        .query(
+            "SELECT statement FROM pg_prepared_statements \
+             WHERE statement LIKE 'SELECT ''lru%' ORDER BY 1",
+            &[],
+        )
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get(0))
+        .collect()
+}
+
+#[monoio::test_all]
+async fn test_statement_cache_lru() {
+    let mut config = config();
+    config.statement_cache_capacity(3);
+    let mut client = Client::connect_with_config(&config).await.unwrap();
+
+    client.query("SELECT 'lru_a'", &[]).await.unwrap();
+    client.query("SELECT 'lru_b'", &[]).await.unwrap();
+    assert_eq!(
+        cached_lru_statements(&mut client).await,
+        ["SELECT 'lru_a'", "SELECT 'lru_b'"]
+    );
+
+    // The listing query itself is cached too, so this evicts `lru_a`.
+    client.query("SELECT 'lru_d'", &[]).await.unwrap();
+    assert_eq!(
+        cached_lru_statements(&mut client).await,
+        ["SELECT 'lru_b'", "SELECT 'lru_d'"]
+    );
+
+    client.clear_statement_cache();
+    assert!(cached_lru_statements(&mut client).await.is_empty());
+    client.query("SELECT 'lru_a'", &[]).await.unwrap();
+}
+
+#[monoio::test_all]
+async fn test_statement_cache_disabled() {
+    let config: Config = format!(
+        "postgres://{}:{}@{}/postgres?statement_cache_capacity=0",
+        USER, PASS, HOST
+    )
+    .parse()
+    .unwrap();
+    assert_eq!(config.get_statement_cache_capacity(), 0);
+    let mut client = Client::connect_with_config(&config).await.unwrap();
+
+    let rows = client.query("SELECT $1::int4 + 1", &[&1i32]).await.unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 2);
+    assert_eq!(client.execute("SELECT 1", &[]).await.unwrap(), 1);
+
+    let mut stream = client.query_stream("SELECT 'lru_x'", &[]).await.unwrap();
+    assert!(stream.next().await.unwrap().is_ok());
+    drop(stream);
+
+    let mut pipeline = client.pipeline();
+    pipeline.query("SELECT $1::int4", &[&1i32]).await.unwrap();
+    pipeline.query("SELECT $1::text", &[&"two"]).await.unwrap();
+    let results = pipeline.run().await.unwrap();
+    let two: String = results[1].as_ref().unwrap().0[0].get(0);
+    assert_eq!(two, "two");
+
+    let rows = client
+        .query("SELECT count(*) FROM pg_prepared_statements", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i64>(0), 0);
+}
+
+#[monoio::test_all]
+async fn test_stale_cached_statement() {
+    let mut client = get_client().await;
+    client
+        .batch_execute("CREATE TEMP TABLE stale_test (a INT); INSERT INTO stale_test VALUES (1)")
+        .await
+        .unwrap();
+
+    let query = "SELECT * FROM stale_test";
+    assert_eq!(client.query(query, &[]).await.unwrap()[0].data.len(), 1);
+    client
+        .batch_execute("ALTER TABLE stale_test ADD COLUMN b INT")
+        .await
+        .unwrap();
+    assert_eq!(client.query(query, &[]).await.unwrap()[0].data.len(), 2);
+
+    // Inside a transaction the error has aborted it, so it is not retried,
+    // but the statement is still prepared again afterwards.
+    let mut tx = client.transaction().await.unwrap();
+    tx.batch_execute("ALTER TABLE stale_test ADD COLUMN c INT")
+        .await
+        .unwrap();
+    let err = tx.query(query, &[]).await.err().unwrap();
+    assert_eq!(err.code(), Some(&SqlState::FeatureNotSupported));
+    tx.rollback().await.unwrap();
+    client
+        .batch_execute("ALTER TABLE stale_test ADD COLUMN c INT")
+        .await
+        .unwrap();
+    assert_eq!(client.query(query, &[]).await.unwrap()[0].data.len(), 3);
+}
+
+#[monoio::test_all]
+async fn test_unsupported_feature_not_retried() {
+    let mut client = get_client().await;
+    // Sequences are not transactional, so the count survives the error.
+    client
+        .batch_execute(
+            "CREATE TEMP SEQUENCE unsupported_calls; \
+             CREATE FUNCTION pg_temp.unsupported() RETURNS INT AS $$ \
+             BEGIN \
+                 PERFORM nextval('unsupported_calls'); \
+                 RAISE EXCEPTION 'not here' USING ERRCODE = 'feature_not_supported'; \
+             END $$ LANGUAGE plpgsql",
+        )
+        .await
+        .unwrap();
+
+    let err = client
+        .query("SELECT pg_temp.unsupported()", &[])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Some(&SqlState::FeatureNotSupported));
+    let rows = client
+        .query("SELECT last_value FROM unsupported_calls", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i64>(0), 1);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_cancel_query() {
+    let mut client = get_client().await;
+    let token = client.cancel_token();
+    assert_eq!(token.process_id(), Some(backend_pid(&mut client).await));
+
+    let canceller = token.clone();
+    monoio::spawn(async move {
+        monoio::time::sleep(std::time::Duration::from_millis(100)).await;
+        canceller.cancel_query().await.unwrap();
+    });
+    let err = client
+        .query("SELECT pg_sleep(10)", &[])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Some(&SqlState::QueryCanceled));
+
+    // The token is `Send`, so another thread's runtime can use it too.
+    let thread = std::thread::spawn(move || {
+        monoio::RuntimeBuilder::<monoio::FusionDriver>::new()
+            .enable_timer()
+            .build()
+            .unwrap()
+            .block_on(async move {
+                monoio::time::sleep(std::time::Duration::from_millis(100)).await;
+                token.cancel_query().await.unwrap();
+            })
+    });
+    let err = client
+        .query("SELECT pg_sleep(10)", &[])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Some(&SqlState::QueryCanceled));
+    thread.join().unwrap();
+
+    assert_eq!(client.execute("SELECT 1", &[]).await.unwrap(), 1);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_query_timeout() {
+    let mut client = get_client().await;
+    let token = client.cancel_token();
+    let timeout = std::time::Duration::from_millis(100);
+
+    let rows = token
+        .timeout(timeout, client.query("SELECT 1", &[]))
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+
+    let start = std::time::Instant::now();
+    let res = token
+        .timeout(timeout, client.query("SELECT pg_sleep(10)", &[]))
+        .await;
+    assert!(matches!(res, Err(Error::Timeout(_))));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+    assert_eq!(client.execute("SELECT 1", &[]).await.unwrap(), 1);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_listen_notify() {
+    let mut listener = get_client().await;
+    let mut sender = get_client().await;
+    let sender_pid = backend_pid(&mut sender).await;
+    listener.listen("Cache \"Events\"").await.unwrap();
+
+    // A notification sent by another session while the listener is idle.
+    monoio::spawn(async move {
+        monoio::time::sleep(std::time::Duration::from_millis(50)).await;
+        sender
+            .execute("SELECT pg_notify('Cache \"Events\"', 'users:1')", &[])
+            .await
+            .unwrap();
+        sender
+    });
+    let mut notifications = listener.notifications();
+    let notification = notifications.next().await.unwrap().unwrap();
+    assert_eq!(notification.channel, "Cache \"Events\"");
+    assert_eq!(notification.payload, "users:1");
+    assert_eq!(notification.process_id, sender_pid);
+    assert!(notifications.try_next().is_none());
+
+    // Notifications that arrive during a query are kept for later.
+    listener
+        .batch_execute("NOTIFY \"Cache \"\"Events\"\"\", 'a'; NOTIFY \"Cache \"\"Events\"\"\"")
+        .await
+        .unwrap();
+    assert_eq!(listener.execute("SELECT 1", &[]).await.unwrap(), 1);
+    let mut notifications = listener.notifications();
+    assert_eq!(notifications.next().await.unwrap().unwrap().payload, "a");
+    assert_eq!(notifications.try_next().unwrap().payload, "");
+    assert!(notifications.try_next().is_none());
+
+    listener.unlisten("Cache \"Events\"").await.unwrap();
+    listener
+        .batch_execute("NOTIFY \"Cache \"\"Events\"\"\", 'ignored'")
+        .await
+        .unwrap();
+    assert!(listener.notifications().try_next().is_none());
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_notifications_next_timeout() {
+    let mut listener = get_client().await;
+    let mut sender = get_client().await;
+    listener.listen("next_timeout").await.unwrap();
+
+    let timeout = std::time::Duration::from_millis(50);
+    assert!(
+        listener
+            .notifications()
+            .next_timeout(timeout)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(!listener.is_closed());
+
+    monoio::spawn(async move {
+        monoio::time::sleep(std::time::Duration::from_millis(50)).await;
+        sender
+            .batch_execute("NOTIFY next_timeout, 'late'")
+            .await
+            .unwrap();
+        sender
+    });
+    let notification = listener
+        .notifications()
+        .next_timeout(std::time::Duration::from_secs(5))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(notification.payload, "late");
+    assert_eq!(listener.execute("SELECT 1", &[]).await.unwrap(), 1);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_notifications_dropped_next_closes_client() {
+    let mut listener = get_client().await;
+    listener.listen("dropped_next").await.unwrap();
+
+    let mut notifications = listener.notifications();
+    let res =
+        monoio::time::timeout(std::time::Duration::from_millis(50), notifications.next()).await;
+    assert!(res.is_err());
+    assert!(listener.is_closed());
+}
+
+#[monoio::test_all]
+async fn test_notice_handler() {
+    let notices = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut config = config();
+    let seen = notices.clone();
+    config.notice_handler(move |notice| seen.lock().unwrap().push(notice.clone()));
+    let mut client = Client::connect_with_config(&config).await.unwrap();
+
+    client
+        .batch_execute("DROP TABLE IF EXISTS no_such_table_for_notice")
+        .await
+        .unwrap();
+    client
+        .execute(
+            "DO $$ BEGIN RAISE WARNING 'careful' USING DETAIL = 'details'; END $$",
+            &[],
+        )
+        .await
+        .unwrap();
+    {
+        let notices = notices.lock().unwrap();
+        assert_eq!(notices.len(), 2);
+        assert_eq!(notices[0].severity, "NOTICE");
+        assert_eq!(notices[0].code, SqlState::SuccessfulCompletion);
+        assert!(notices[0].message.contains("does not exist, skipping"));
+        assert_eq!(notices[1].severity, "WARNING");
+        assert_eq!(notices[1].code, SqlState::Warning);
+        assert_eq!(notices[1].message, "careful");
+        assert_eq!(notices[1].detail.as_deref(), Some("details"));
+    }
+
+    let replaced = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = replaced.clone();
+    client.set_notice_handler(move |notice| seen.lock().unwrap().push(notice.message.clone()));
+    client
+        .execute("DO $$ BEGIN RAISE NOTICE 'hello %', 1; END $$", &[])
+        .await
+        .unwrap();
+    assert_eq!(*replaced.lock().unwrap(), ["hello 1"]);
+    assert_eq!(notices.lock().unwrap().len(), 2);
+}
+
+#[monoio::test_all]
+async fn test_server_parameters() {
+    let mut client = get_client().await;
+
+    let version: String = client.query("SHOW server_version_num", &[]).await.unwrap()[0].get(0);
+    assert_eq!(client.server_version(), Some(version.parse().unwrap()));
+    assert_eq!(client.parameter("client_encoding"), Some("UTF8"));
+    assert_eq!(client.parameter("no_such_parameter"), None);
+
+    client
+        .batch_execute("SET TimeZone = 'Asia/Tokyo'")
+        .await
+        .unwrap();
+    assert_eq!(client.parameter("TimeZone"), Some("Asia/Tokyo"));
+    client
+        .execute(
+            "SELECT set_config('application_name', 'renamed', false)",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(client.parameter("application_name"), Some("renamed"));
+}
+
+#[monoio::test_all]
+async fn test_transaction_status() {
+    let mut client = get_client().await;
+    assert_eq!(client.transaction_status(), TransactionStatus::Idle);
+
+    client.batch_execute("BEGIN").await.unwrap();
+    assert_eq!(
+        client.transaction_status(),
+        TransactionStatus::InTransaction
+    );
+    assert!(client.execute("SELECT 1/0", &[]).await.is_err());
+    assert_eq!(client.transaction_status(), TransactionStatus::Failed);
+    client.batch_execute("ROLLBACK").await.unwrap();
+    assert_eq!(client.transaction_status(), TransactionStatus::Idle);
+
+    let tx = client.transaction().await.unwrap();
+    drop(tx);
+    // The rollback is only sent with the next request.
+    assert_eq!(
+        client.transaction_status(),
+        TransactionStatus::InTransaction
+    );
+    client.execute("SELECT 1", &[]).await.unwrap();
+    assert_eq!(client.transaction_status(), TransactionStatus::Idle);
+}
+
+#[monoio::test_all]
+async fn test_pool_rolls_back_open_transaction() {
+    let pool = Pool::builder(config()).max_size(1).build();
+
+    let mut client = pool.get().await.unwrap();
+    client.batch_execute("BEGIN").await.unwrap();
+    drop(client);
+
+    let mut client = pool.get().await.unwrap();
+    client.execute("SELECT 1", &[]).await.unwrap();
+    assert_eq!(client.transaction_status(), TransactionStatus::Idle);
+}
+
+#[derive(Debug, PartialEq, ToSql, FromSql)]
+#[postgres(name = "mood")]
+enum Mood {
+    #[postgres(name = "happy")]
+    Happy,
+    #[postgres(name = "sad")]
+    Sad,
+}
+
+#[derive(Debug, PartialEq, ToSql, FromSql)]
+#[postgres(name = "inventory_item")]
+struct InventoryItem {
+    name: String,
+    supplier_id: i32,
+    price: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, ToSql, FromSql)]
+#[postgres(name = "positive")]
+struct Positive(i32);
+
+#[monoio::test_all]
+async fn test_custom_types() {
+    let mut client = get_client().await;
+    client
+        .batch_execute(
+            "CREATE TYPE pg_temp.mood AS ENUM ('happy', 'sad');
+             CREATE TYPE pg_temp.inventory_item AS (name TEXT, supplier_id INT4, price FLOAT8);
+             CREATE DOMAIN pg_temp.positive AS INT4 CHECK (VALUE > 0);
+             CREATE TYPE pg_temp.floatrange AS RANGE (subtype = FLOAT8)",
+        )
+        .await
+        .unwrap();
+
+    let item = InventoryItem {
+        name: "widget".into(),
+        supplier_id: 42,
+        price: None,
+    };
+    let rows = client
+        .query(
+            "SELECT $1::mood, $2::mood[], $3::inventory_item, $4::positive",
+            &[
+                &Mood::Sad,
+                &vec![Mood::Happy, Mood::Sad],
+                &item,
+                &Positive(3),
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, Mood>(0), Mood::Sad);
+    assert_eq!(rows[0].get::<_, Vec<Mood>>(1), [Mood::Happy, Mood::Sad]);
+    assert_eq!(rows[0].get::<_, InventoryItem>(2), item);
+    assert_eq!(rows[0].get::<_, Positive>(3), Positive(3));
+
+    let mood = rows[0].columns()[0].type_.clone();
+    assert_eq!(mood.name(), "mood");
+    assert_eq!(mood.kind(), &Kind::Enum(vec!["happy".into(), "sad".into()]));
+    assert_eq!(rows[0].columns()[1].type_.kind(), &Kind::Array(mood));
+    let Kind::Composite(fields) = rows[0].columns()[2].type_.kind() else {
+        panic!("inventory_item is not a composite");
+    };
+    assert_eq!(fields[1].name(), "supplier_id");
+    assert_eq!(fields[1].type_(), &Type::INT4);
+
+    let stmt = client
+        .prepare("SELECT '[1.5, 2.5)'::floatrange, $1::positive")
+        .await
+        .unwrap();
+    assert_eq!(stmt.columns()[0].type_.kind(), &Kind::Range(Type::FLOAT8));
+    assert_eq!(stmt.params()[0].kind(), &Kind::Domain(Type::INT4));
+    assert!(client.query(&stmt, &[&3i32]).await.is_err());
+}
+
+#[monoio::test_all]
+async fn test_extension_type() {
+    let mut client = get_client().await;
+    client
+        // The lock keeps the tests for each driver from racing to create it.
+        .batch_execute(
+            "BEGIN; SELECT pg_advisory_xact_lock(19); \
+             CREATE EXTENSION IF NOT EXISTS citext; COMMIT",
+        )
+        .await
+        .unwrap();
+
+    let rows = client
+        .query("SELECT $1::citext = 'HELLO', $1::citext", &[&"hello"])
+        .await
+        .unwrap();
+    assert!(rows[0].get::<_, bool>(0));
+    assert_eq!(rows[0].columns()[1].type_.name(), "citext");
+    assert_eq!(rows[0].get::<_, &str>(1), "hello");
+}
+
+#[monoio::test_all]
+async fn test_custom_types_cached_per_connection() {
+    let mut client = get_client().await;
+    client
+        .batch_execute("CREATE TYPE pg_temp.mood AS ENUM ('happy', 'sad')")
+        .await
+        .unwrap();
+    client.query("SELECT 'sad'::mood", &[]).await.unwrap();
+
+    // The enum's labels were cached when it was first seen.
+    client
+        .batch_execute("ALTER TYPE pg_temp.mood ADD VALUE 'ok'")
+        .await
+        .unwrap();
+    let rows = client.query("SELECT 'ok'::mood", &[]).await.unwrap();
+    assert_eq!(
+        rows[0].columns()[0].type_.kind(),
+        &Kind::Enum(vec!["happy".into(), "sad".into()])
+    );
+
+    client.clear_statement_cache();
+    let rows = client.query("SELECT 'ok'::mood", &[]).await.unwrap();
+    assert_eq!(
+        rows[0].columns()[0].type_.kind(),
+        &Kind::Enum(vec!["happy".into(), "sad".into(), "ok".into()])
+    );
+}
+
+/// Returns the first directory the server puts its Unix socket in.
+async fn socket_dir() -> String {
+    let rows = get_client()
+        .await
+        .query("SHOW unix_socket_directories", &[])
+        .await
+        .unwrap();
+    let dirs: String = rows[0].get(0);
+    dirs.split(',').next().unwrap().trim().to_string()
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_unix_socket() {
+    let dir = socket_dir().await;
+    let mut config = config();
+    config.host(&dir);
+    let mut client = Client::connect_with_config(&config).await.unwrap();
+
+    let rows = client
+        .query("SELECT inet_client_addr() IS NULL", &[])
+        .await
+        .unwrap();
+    assert!(rows[0].get::<_, bool>(0));
+
+    // Cancel requests go over the socket too.
+    let token = client.cancel_token();
+    let res = token
+        .timeout(
+            std::time::Duration::from_millis(100),
+            client.query("SELECT pg_sleep(10)", &[]),
+        )
+        .await;
+    assert!(matches!(res, Err(Error::Timeout(_))));
+
+    let url = format!(
+        "postgres://{}:{}@{}/postgres",
+        USER,
+        PASS,
+        dir.replace('/', "%2F")
+    );
+    let mut client = Client::connect_with_config(&url.parse().unwrap())
+        .await
+        .unwrap();
+    client.execute("SELECT 1", &[]).await.unwrap();
+}
+
+/// Answers a startup message the way a server that trusts every client
+/// would, reporting `params` as parameter statuses, then holds the connection
+/// open until the client goes away. Queries are not answered.
+async fn serve_trusted<S: AsyncReadRent + AsyncWriteRent>(mut stream: S, params: &[(&str, &str)]) {
+    accept_startup(&mut stream, params).await;
+    loop {
+        let (res, _) = stream.read(vec![0u8; 1024]).await;
+        if !matches!(res, Ok(n) if n > 0) {
+            break;
+        }
+    }
+}
+
+/// Reads the startup message and lets the client in without a password.
+async fn accept_startup<S: AsyncReadRent + AsyncWriteRent>(
+    stream: &mut S,
+    params: &[(&str, &str)],
+) {
+    let (res, len) = stream.read_exact(vec![0u8; 4]).await;
+    res.unwrap();
+    let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    let (res, _) = stream.read_exact(vec![0u8; len - 4]).await;
+    res.unwrap();
+
+    let mut reply = b"R\0\0\0\x08\0\0\0\0".to_vec();
+    for (name, value) in params {
+        reply.push(b'S');
+        reply.extend_from_slice(&((name.len() + value.len() + 6) as u32).to_be_bytes());
+        reply.extend_from_slice(name.as_bytes());
+        reply.push(0);
+        reply.extend_from_slice(value.as_bytes());
+        reply.push(0);
+    }
+    reply.extend_from_slice(b"Z\0\0\0\x05I");
+    let (res, _) = stream.write_all(reply).await;
+    res.unwrap();
+}
+
+/// Starts a [`serve_trusted`] server on a free local port and returns the
+/// port.
+fn trusted_server(params: &'static [(&'static str, &'static str)]) -> u16 {
+    let listener = monoio::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    monoio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            monoio::spawn(serve_trusted(stream, params));
+        }
+    });
+    port
+}
+
+#[monoio::test_all]
+async fn test_unix_socket_without_password() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "monoio-pg-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let listener = monoio::net::UnixListener::bind_with_config(
+        dir.join(".s.PGSQL.5432"),
+        &monoio::net::ListenerOpts::new().reuse_port(false),
+    )
+    .unwrap();
+    // A server that trusts the peer, as with `peer` authentication.
+    monoio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        serve_trusted(stream, &[]).await;
+    });
+
+    let mut config = Config::new();
+    config.host(dir.to_str().unwrap()).user(USER);
+    let client = Client::connect_with_config(&config).await.unwrap();
+    assert_eq!(client.transaction_status(), TransactionStatus::Idle);
+    drop(client);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[monoio::test_all]
+async fn test_multiple_hosts_failover() {
+    // Nothing listens on port 1, so the second host is used.
+    let mut config = config();
+    config.host("127.0.0.1,127.0.0.1").ports(&[1, 5432]);
+    let mut client = Client::connect_with_config(&config).await.unwrap();
+    client.execute("SELECT 1", &[]).await.unwrap();
+
+    config.ports(&[1]);
+    assert!(matches!(
+        Client::connect_with_config(&config).await,
+        Err(Error::Io(_))
+    ));
+}
+
+const STANDBY: &[(&str, &str)] = &[
+    ("in_hot_standby", "on"),
+    ("default_transaction_read_only", "off"),
+];
+
+/// Connects with `attrs` to the given ports on 127.0.0.1, and returns whether
+/// the server reached is in hot standby.
+async fn connect_with_attrs(ports: &[u16], attrs: TargetSessionAttrs) -> Result<bool, Error> {
+    let mut config = config();
+    config
+        .host(&vec!["127.0.0.1"; ports.len()].join(","))
+        .ports(ports)
+        .target_session_attrs(attrs);
+    let client = Client::connect_with_config(&config).await?;
+    Ok(client.parameter("in_hot_standby") == Some("on"))
+}
+
+#[monoio::test_all]
+async fn test_target_session_attrs() {
+    let standby = trusted_server(STANDBY);
+    let primary = 5432;
+    let both = [standby, primary];
+
+    assert!(
+        connect_with_attrs(&both, TargetSessionAttrs::Any)
+            .await
+            .unwrap()
+    );
+    assert!(
+        !connect_with_attrs(&both, TargetSessionAttrs::Primary)
+            .await
+            .unwrap()
+    );
+    assert!(
+        !connect_with_attrs(&both, TargetSessionAttrs::ReadWrite)
+            .await
+            .unwrap()
+    );
+    assert!(
+        connect_with_attrs(&[primary, standby], TargetSessionAttrs::Standby)
+            .await
+            .unwrap()
+    );
+    assert!(
+        connect_with_attrs(&[primary, standby], TargetSessionAttrs::ReadOnly)
+            .await
+            .unwrap()
+    );
+    assert!(
+        connect_with_attrs(&[primary, standby], TargetSessionAttrs::PreferStandby)
+            .await
+            .unwrap()
+    );
+
+    // With no standby to be had, prefer-standby settles for the primary.
+    assert!(
+        !connect_with_attrs(&[primary], TargetSessionAttrs::PreferStandby)
+            .await
+            .unwrap()
+    );
+    assert!(
+        connect_with_attrs(&[primary], TargetSessionAttrs::Standby)
+            .await
+            .is_err()
+    );
+    assert!(
+        connect_with_attrs(&[standby], TargetSessionAttrs::Primary)
+            .await
+            .is_err()
+    );
+
+    // A server can also be read-only by default without being a standby.
+    let mut config = config();
+    config.param("default_transaction_read_only", "on");
+    config.target_session_attrs(TargetSessionAttrs::ReadWrite);
+    assert!(Client::connect_with_config(&config).await.is_err());
+    config.target_session_attrs(TargetSessionAttrs::ReadOnly);
+    Client::connect_with_config(&config).await.unwrap();
+}
+
+#[monoio::test_all]
+async fn test_load_balance_hosts() {
+    let a = trusted_server(&[("application_name", "a")]);
+    let b = trusted_server(&[("application_name", "b")]);
+    let mut config = config();
+    config.host("127.0.0.1,127.0.0.1").ports(&[a, b]);
+
+    let mut seen = std::collections::HashSet::new();
+    for _ in 0..8 {
+        let client = Client::connect_with_config(&config).await.unwrap();
+        seen.insert(client.parameter("application_name").unwrap().to_string());
+    }
+    assert_eq!(seen.len(), 1);
+
+    config.load_balance_hosts(LoadBalanceHosts::Random);
+    for _ in 0..32 {
+        let client = Client::connect_with_config(&config).await.unwrap();
+        seen.insert(client.parameter("application_name").unwrap().to_string());
+    }
+    assert_eq!(seen.len(), 2);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_connect_timeout() {
+    // A listener that never accepts: the TCP handshake completes, but the
+    // startup message is never answered.
+    let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let silent_port = silent.local_addr().unwrap().port();
+
+    let mut config = config();
+    config
+        .host("127.0.0.1")
+        .port(silent_port)
+        .connect_timeout(std::time::Duration::from_secs(1));
+    let start = std::time::Instant::now();
+    assert!(matches!(
+        Client::connect_with_config(&config).await,
+        Err(Error::Timeout(_))
+    ));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+    // The next host is tried once the first times out. The timeout leaves
+    // room for authentication, which is slow in debug builds.
+    config
+        .host("127.0.0.1,127.0.0.1")
+        .ports(&[silent_port, 5432]);
+    let mut client = Client::connect_with_config(&config).await.unwrap();
+    client.execute("SELECT 1", &[]).await.unwrap();
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_statement_timeout() {
+    let mut config = config();
+    config.statement_timeout(std::time::Duration::from_millis(100));
+    let mut client = Client::connect_with_config(&config).await.unwrap();
+
+    assert_eq!(client.execute("SELECT 1", &[]).await.unwrap(), 1);
+    let start = std::time::Instant::now();
+    assert!(matches!(
+        client.query("SELECT pg_sleep(10)", &[]).await,
+        Err(Error::Timeout(_))
+    ));
+    assert!(matches!(
+        client.batch_execute("SELECT pg_sleep(10)").await,
+        Err(Error::Timeout(_))
+    ));
+    assert!(matches!(
+        client.simple_query("SELECT pg_sleep(10)").await,
+        Err(Error::Timeout(_))
+    ));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+    // The cancelled queries leave the connection ready for the next one.
+    assert_eq!(client.query("SELECT 1", &[]).await.unwrap().len(), 1);
+
+    client.set_statement_timeout(None);
+    client.execute("SELECT pg_sleep(0.2)", &[]).await.unwrap();
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_statement_timeout_uncancellable() {
+    // The server never answers the query and sends no cancellation key.
+    let port = trusted_server(&[]);
+    let mut config = config();
+    config
+        .host("127.0.0.1")
+        .port(port)
+        .statement_timeout(std::time::Duration::from_millis(50));
+    let pool = Pool::builder(config).build();
+
+    let mut client = pool.get().await.unwrap();
+    let start = std::time::Instant::now();
+    assert!(matches!(
+        client.execute("SELECT 1", &[]).await,
+        Err(Error::Timeout(_))
+    ));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+    // Abandoned partway through a response, the connection is discarded.
+    drop(client);
+    assert_eq!(pool.size(), 0);
+}
+
+#[monoio::test_all]
+async fn test_protocol_error_drains_response() {
+    let listener = monoio::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    monoio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        accept_startup(&mut stream, &[]).await;
+        // The first query's command tag is not valid UTF-8; the second one's
+        // response is well formed.
+        let replies: [&[u8]; 2] = [
+            b"C\0\0\0\x06\xff\0Z\0\0\0\x05I",
+            b"C\0\0\0\x0dSELECT 0\0Z\0\0\0\x05I",
+        ];
+        for reply in replies {
+            let (res, header) = stream.read_exact(vec![0u8; 5]).await;
+            res.unwrap();
+            let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+            let (res, _) = stream.read_exact(vec![0u8; len - 4]).await;
+            res.unwrap();
+            let (res, _) = stream.write_all(reply.to_vec()).await;
+            res.unwrap();
+        }
+    });
+    let mut config = config();
+    config.host("127.0.0.1").port(port);
+    let mut client = Client::connect_with_config(&config).await.unwrap();
+
+    assert!(matches!(
+        client.simple_query("SELECT 1").await,
+        Err(Error::Protocol(_))
+    ));
+    assert!(!client.is_closed());
+
+    // The rest of the first response is discarded before the second query.
+    let results = client.simple_query("SELECT 2").await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].tag, CommandTag::Select(0));
+}
+
+#[monoio::test_all]
+async fn test_client_close_sends_terminate() {
+    let listener = monoio::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = monoio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        accept_startup(&mut stream, &[]).await;
+        let (res, message) = stream.read_exact(vec![0u8; 5]).await;
+        res.unwrap();
+        message
+    });
+    let mut config = config();
+    config.host("127.0.0.1").port(port);
+    let client = Client::connect_with_config(&config).await.unwrap();
 
-    assert_eq!(t_val, true);
-    assert_eq!(f_val, false);
+    // Returns once the server has closed its end.
+    client.close().await.unwrap();
+    assert_eq!(server.await, b"X\0\0\0\x04");
 }
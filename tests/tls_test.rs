@@ -0,0 +1,294 @@
+use monoio::io::{AsyncReadRent, AsyncReadRentExt, AsyncWriteRent, AsyncWriteRentExt};
+use monoio::net::TcpListener;
+use monoio_pg::{Client, Config, Error, SslMode, TlsConfig};
+use monoio_rustls::TlsAcceptor;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+struct Cert {
+    der: CertificateDer<'static>,
+    pem: String,
+    key: Vec<u8>,
+}
+
+fn self_signed(name: &str) -> Cert {
+    let certified = rcgen::generate_simple_self_signed(vec![name.to_string()]).unwrap();
+    Cert {
+        der: certified.cert.der().clone(),
+        pem: certified.cert.pem(),
+        key: certified.key_pair.serialize_der(),
+    }
+}
+
+/// Reads a startup message and accepts it without asking for a password.
+/// Returns the process ID and secret key if it was a `CancelRequest` instead.
+async fn serve_startup<S: AsyncReadRent + AsyncWriteRent>(stream: &mut S) -> Option<(i32, i32)> {
+    let (res, len) = stream.read_exact(vec![0u8; 4]).await;
+    res.unwrap();
+    let len = i32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    let (res, body) = stream.read_exact(vec![0u8; len - 4]).await;
+    res.unwrap();
+    let int = |i: usize| i32::from_be_bytes([body[i], body[i + 1], body[i + 2], body[i + 3]]);
+    if int(0) == 80877102 {
+        return Some((int(4), int(8)));
+    }
+
+    let mut reply = Vec::new();
+    reply.extend_from_slice(b"R\x00\x00\x00\x08\x00\x00\x00\x00"); // AuthenticationOk
+    reply.extend_from_slice(b"K\x00\x00\x00\x0c\x00\x00\x00\x2a\x00\x00\x00\x07"); // BackendKeyData
+    reply.extend_from_slice(b"Z\x00\x00\x00\x05I"); // ReadyForQuery
+    let (res, _) = stream.write_all(reply).await;
+    res.unwrap();
+    stream.flush().await.unwrap();
+    None
+}
+
+/// Starts a mock server that answers each `SSLRequest` with `answer`, then
+/// completes the startup handshake over TLS (for `b'S'`) or plain TCP (for
+/// `b'N'`).
+fn mock_server(answer: u8, cert: &Cert) -> u16 {
+    mock_server_with_cancels(answer, cert).0
+}
+
+/// A backend's process ID and secret key.
+type CancelKey = (i32, i32);
+
+/// Like [`mock_server`], but also returns the keys of the `CancelRequest`s
+/// it has received.
+fn mock_server_with_cancels(answer: u8, cert: &Cert) -> (u16, Rc<RefCell<Vec<CancelKey>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server_config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_no_client_auth()
+    .with_single_cert(
+        vec![cert.der.clone()],
+        PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.key.clone())),
+    )
+    .unwrap();
+    let acceptor = Rc::new(TlsAcceptor::from(server_config));
+    let cancels = Rc::new(RefCell::new(Vec::new()));
+
+    let received = cancels.clone();
+    monoio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            let received = received.clone();
+            monoio::spawn(async move {
+                let (res, request) = stream.read_exact(vec![0u8; 8]).await;
+                res.unwrap();
+                assert_eq!(request, b"\x00\x00\x00\x08\x04\xd2\x16\x2f");
+
+                let (res, _) = stream.write_all(vec![answer]).await;
+                res.unwrap();
+
+                let cancel = if answer == b'S' {
+                    // The client may give up on the certificate, so handshake
+                    // errors are expected in some tests.
+                    match acceptor.accept(stream).await {
+                        Ok(mut tls) => {
+                            let cancel = serve_startup(&mut tls).await;
+                            if cancel.is_none() {
+                                let _ = tls.read(vec![0u8; 1]).await;
+                            }
+                            cancel
+                        }
+                        Err(_) => None,
+                    }
+                } else {
+                    let cancel = serve_startup(&mut stream).await;
+                    if cancel.is_none() {
+                        let _ = stream.read(vec![0u8; 1]).await;
+                    }
+                    cancel
+                };
+                received.borrow_mut().extend(cancel);
+            });
+        }
+    });
+    (port, cancels)
+}
+
+async fn connect(host: &str, port: u16, tls_config: &TlsConfig) -> monoio_pg::Result<Client> {
+    let mut config = Config::new();
+    config
+        .host(host)
+        .port(port)
+        .user("monoio")
+        .tls_config(tls_config.clone());
+    Client::connect_with_config(&config).await
+}
+
+#[monoio::test_all]
+async fn test_tls_require() {
+    let cert = self_signed("localhost");
+    let port = mock_server(b'S', &cert);
+
+    connect("127.0.0.1", port, &TlsConfig::new(SslMode::Require))
+        .await
+        .unwrap();
+}
+
+#[monoio::test_all]
+async fn test_tls_require_rejected() {
+    let cert = self_signed("localhost");
+    let port = mock_server(b'N', &cert);
+
+    let result = connect("127.0.0.1", port, &TlsConfig::new(SslMode::Require)).await;
+    assert!(matches!(result, Err(Error::Tls(_))));
+}
+
+#[monoio::test_all]
+async fn test_tls_prefer_falls_back() {
+    let cert = self_signed("localhost");
+    let port = mock_server(b'N', &cert);
+
+    connect("127.0.0.1", port, &TlsConfig::new(SslMode::Prefer))
+        .await
+        .unwrap();
+}
+
+#[monoio::test_all]
+async fn test_tls_verify_full() {
+    let cert = self_signed("localhost");
+    let port = mock_server(b'S', &cert);
+
+    let mut tls_config = TlsConfig::new(SslMode::VerifyFull);
+    tls_config
+        .add_root_certificates_pem(cert.pem.as_bytes())
+        .unwrap();
+    connect("localhost", port, &tls_config).await.unwrap();
+}
+
+#[monoio::test_all]
+async fn test_tls_verify_full_wrong_host() {
+    let cert = self_signed("db.example.com");
+    let port = mock_server(b'S', &cert);
+
+    let mut tls_config = TlsConfig::new(SslMode::VerifyFull);
+    tls_config.add_root_certificate(cert.der.clone());
+    let result = connect("localhost", port, &tls_config).await;
+    assert!(matches!(result, Err(Error::Tls(_))));
+}
+
+#[monoio::test_all]
+async fn test_tls_verify_ca_ignores_host() {
+    let cert = self_signed("db.example.com");
+    let port = mock_server(b'S', &cert);
+
+    let mut tls_config = TlsConfig::new(SslMode::VerifyCa);
+    tls_config.add_root_certificate(cert.der.clone());
+    connect("localhost", port, &tls_config).await.unwrap();
+}
+
+#[monoio::test_all]
+async fn test_tls_verify_ca_untrusted() {
+    let cert = self_signed("localhost");
+    let other = self_signed("localhost");
+    let port = mock_server(b'S', &cert);
+
+    let mut tls_config = TlsConfig::new(SslMode::VerifyCa);
+    tls_config.add_root_certificate(other.der);
+    let result = connect("localhost", port, &tls_config).await;
+    assert!(matches!(result, Err(Error::Tls(_))));
+}
+
+#[monoio::test_all]
+async fn test_tls_sslrootcert_from_connection_string() {
+    let cert = self_signed("localhost");
+    let port = mock_server(b'S', &cert);
+
+    let path = std::env::temp_dir().join(format!("monoio-pg-root-{}.crt", port));
+    std::fs::write(&path, &cert.pem).unwrap();
+    let config: Config = format!(
+        "postgres://monoio@localhost:{}/?sslmode=verify-full&sslrootcert={}",
+        port,
+        path.display()
+    )
+    .parse()
+    .unwrap();
+    let result = Client::connect_with_config(&config).await;
+    std::fs::remove_file(&path).unwrap();
+    result.unwrap();
+}
+
+#[monoio::test_all]
+async fn test_tls_cancel_request() {
+    let cert = self_signed("localhost");
+    let (port, cancels) = mock_server_with_cancels(b'S', &cert);
+
+    let client = connect("127.0.0.1", port, &TlsConfig::new(SslMode::Require))
+        .await
+        .unwrap();
+    let token = client.cancel_token();
+    assert_eq!(token.process_id(), Some(42));
+    token.cancel_query().await.unwrap();
+    assert_eq!(*cancels.borrow(), [(42, 7)]);
+}
+
+#[monoio::test_all(timer_enabled = true)]
+async fn test_tls_notifications_next_timeout() {
+    let cert = self_signed("localhost");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let acceptor = TlsAcceptor::from(
+        rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![cert.der.clone()],
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.key.clone())),
+        )
+        .unwrap(),
+    );
+    monoio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let (res, _) = stream.read_exact(vec![0u8; 8]).await;
+        res.unwrap();
+        let (res, _) = stream.write_all(b"S".to_vec()).await;
+        res.unwrap();
+        let mut tls = acceptor.accept(stream).await.unwrap();
+        serve_startup(&mut tls).await;
+
+        // Each poll is an empty query. A notification comes with the second.
+        let replies: [&[u8]; 2] = [
+            b"I\0\0\0\x04Z\0\0\0\x05I",
+            b"A\0\0\0\x0c\0\0\0\x2ac\0p\0I\0\0\0\x04Z\0\0\0\x05I",
+        ];
+        for reply in replies {
+            let (res, header) = tls.read_exact(vec![0u8; 5]).await;
+            res.unwrap();
+            assert_eq!(header[0], b'Q');
+            let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+            let (res, _) = tls.read_exact(vec![0u8; len - 4]).await;
+            res.unwrap();
+            let (res, _) = tls.write_all(reply.to_vec()).await;
+            res.unwrap();
+            tls.flush().await.unwrap();
+        }
+        let _ = tls.read(vec![0u8; 1]).await;
+    });
+
+    let mut client = connect("127.0.0.1", port, &TlsConfig::new(SslMode::Require))
+        .await
+        .unwrap();
+    let mut notifications = client.notifications();
+    let timeout = std::time::Duration::from_millis(50);
+    assert!(notifications.next_timeout(timeout).await.unwrap().is_none());
+
+    let timeout = std::time::Duration::from_secs(5);
+    let notification = notifications.next_timeout(timeout).await.unwrap().unwrap();
+    assert_eq!(notification.channel, "c");
+    assert_eq!(notification.payload, "p");
+    assert!(!client.is_closed());
+}